@@ -0,0 +1,16 @@
+#![no_main]
+
+use std::collections::HashSet;
+
+use captain_sonar::radar::{parse_log, Map, Radar};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(log) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    // malformed input has to be rejected with an error, never a panic
+    let _ = parse_log(log);
+    let _ = Radar::new(Map::new(15, HashSet::new())).register_log(log);
+});
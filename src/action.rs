@@ -0,0 +1,51 @@
+use crate::{intel::IntelQuestion, radar::Direction};
+
+/// Everything a user can do to the tracker, independent of how it was input.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    /// a single move in a direction
+    Move(Direction),
+    /// `count` moves in the same direction, stopping at the first illegal one
+    Burst {
+        direction: Direction,
+        count: usize,
+    },
+    Silence,
+    /// surfacing, after which the enemy may cross its old trail
+    Surface,
+    Mine,
+    /// intel from a drone, sonar, torpedo or similar
+    Intel(IntelQuestion),
+    Undo,
+    /// undoes everything after the latest intel
+    UndoToLastIntel,
+    /// undoes the latest surface and everything after it
+    UndoToLastSurface,
+    /// drops all intel but keeps the moves
+    ClearIntel,
+    /// replaces the silence at a trace index with moves, see [`crate::radar::Radar::commit_silence`]
+    CommitSilence {
+        index: usize,
+        direction: Direction,
+        distance: u8,
+    },
+    /// cycles the shown path
+    NextPath,
+    ToggleHelp,
+    /// shows only the given number of most likely positions instead of a path, or goes back
+    ToggleTop(usize),
+    /// switches the grid between showing a path and the cells reachable in the next turns
+    ToggleCone,
+    /// changes how many turns ahead the reachable cells are shown, by the given amount
+    AdjustCone(isize),
+    /// switches the grid between showing a path and the probability of each cell
+    ToggleNumbers,
+    /// switches the grid between box-drawing and ASCII characters
+    ToggleCharset,
+    /// shows or hides the part of the path from before the latest surface
+    ToggleGhost,
+    /// switches between drawing the whole map and only the part around the possible positions
+    ToggleFollow,
+    Quit,
+}
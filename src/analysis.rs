@@ -0,0 +1,424 @@
+use alloc::vec::Vec;
+
+use crate::{
+    collections::{HashMap, HashSet},
+    intel::{Quadrant, TORPEDO_RANGE},
+    radar::{Coordinate, DecoratedCoordinate, Direction, Map, Radar},
+};
+
+/// How many of the possible positions a torpedo explosion would damage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TorpedoCoverage {
+    All,
+    Some,
+    None,
+}
+
+/// Why no position is possible, see [`crate::radar::Radar::diagnose_empty`].
+/// Indices refer to elements of the trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyReason {
+    /// The trace visits more cells than the map has open water.
+    MapTooSmall,
+    /// The moves alone stop fitting on the map at `index`, so the enemy must have surfaced before it.
+    ForcedSurface { index: usize },
+    /// Removing only the intel at `index` makes positions possible again.
+    ContradictoryIntel { index: usize },
+    /// No single piece of intel is to blame, several contradict each other.
+    OverConstrained,
+}
+
+/// The cells of `path` since the last surface.
+fn trail(path: &[DecoratedCoordinate]) -> Vec<Coordinate> {
+    let start = path
+        .iter()
+        .rposition(DecoratedCoordinate::surfaced)
+        .unwrap_or(0);
+    path[start..]
+        .iter()
+        .map(DecoratedCoordinate::coord)
+        .collect()
+}
+
+/// All paths the enemy could have taken, with queries derived from them.
+/// Paths may be weighted by likelihood, the heatmap is computed once as most queries use it.
+#[derive(Debug, Clone, Default)]
+pub struct PossibilitySet {
+    paths: Vec<Vec<Coordinate>>,
+    heatmap: HashMap<Coordinate, f64>,
+}
+
+impl PossibilitySet {
+    pub fn new(paths: Vec<Vec<Coordinate>>) -> Self {
+        Self::weighted(paths.into_iter().map(|path| (1.0, path)).collect())
+    }
+
+    pub fn weighted(paths: Vec<(f64, Vec<Coordinate>)>) -> Self {
+        let mut heatmap = HashMap::new();
+        let mut total = 0.0;
+        for (weight, path) in &paths {
+            if let Some(&position) = path.last() {
+                *heatmap.entry(position).or_insert(0.0) += weight;
+                total += weight;
+            }
+        }
+
+        if total > 0.0 {
+            for probability in heatmap.values_mut() {
+                *probability /= total;
+            }
+        }
+
+        Self {
+            paths: paths.into_iter().map(|(_, path)| path).collect(),
+            heatmap,
+        }
+    }
+
+    /// Paths are cut to the part since the last surface, as the rest no longer blocks any move.
+    pub fn from_decorated(paths: &[Vec<DecoratedCoordinate>]) -> Self {
+        Self::new(paths.iter().map(|path| trail(path)).collect())
+    }
+
+    pub fn from_weighted_decorated(paths: &[(f64, Vec<DecoratedCoordinate>)]) -> Self {
+        Self::weighted(
+            paths
+                .iter()
+                .map(|(weight, path)| (*weight, trail(path)))
+                .collect(),
+        )
+    }
+
+    pub fn paths(&self) -> &[Vec<Coordinate>] {
+        &self.paths
+    }
+
+    /// Coordinates the enemy can currently be at, ie. the ends of all paths.
+    pub fn positions(&self) -> HashSet<Coordinate> {
+        self.heatmap.keys().copied().collect()
+    }
+
+    /// Number of distinct possible positions.
+    pub fn count(&self) -> usize {
+        self.heatmap.len()
+    }
+
+    /// Directions in which at least one path can advance by a cell without leaving the map,
+    /// hitting an obstacle or crossing itself.
+    pub fn legal_moves(&self, map: &Map) -> HashSet<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| {
+                self.paths.iter().any(|path| {
+                    path.last()
+                        .and_then(|&last| map.step(last, direction))
+                        .is_some_and(|next| !path.contains(&next))
+                })
+            })
+            .collect()
+    }
+
+    /// Cells reachable after between 1 and `turns` more directed moves from the end of any path,
+    /// see [`Radar::reachable_within`].
+    pub fn reachable_within(&self, map: &Map, turns: usize) -> HashSet<Coordinate> {
+        fn explore(
+            map: &Map,
+            at: Coordinate,
+            visited: &mut HashSet<Coordinate>,
+            turns: usize,
+            reachable: &mut HashSet<Coordinate>,
+        ) {
+            if turns == 0 {
+                return;
+            }
+
+            for direction in Direction::ALL {
+                let Some(next) = map.step(at, direction) else {
+                    continue;
+                };
+                if visited.contains(&next) {
+                    continue;
+                }
+
+                reachable.insert(next);
+                visited.insert(next);
+                explore(map, next, visited, turns - 1, reachable);
+                visited.remove(&next);
+            }
+        }
+
+        let turns = turns.min(Radar::MAX_REACHABLE_TURNS);
+        let mut reachable = HashSet::new();
+        for path in &self.paths {
+            let Some(&last) = path.last() else {
+                continue;
+            };
+            let mut visited = path.iter().copied().collect();
+            explore(map, last, &mut visited, turns, &mut reachable);
+        }
+
+        reachable
+    }
+
+    /// Cells reachable after between 1 and `turns` more directed moves from any possible position,
+    /// see [`Radar::reachable_ignoring_trail`].
+    pub fn reachable_ignoring_trail(&self, map: &Map, turns: usize) -> HashSet<Coordinate> {
+        let mut reachable = HashSet::new();
+        let mut frontier = self.positions();
+        for _ in 0..turns {
+            frontier = frontier
+                .iter()
+                .flat_map(|&at| {
+                    Direction::ALL
+                        .into_iter()
+                        .filter_map(move |d| map.step(at, d))
+                })
+                .collect();
+            if frontier.is_subset(&reachable) {
+                break;
+            }
+            reachable.extend(frontier.iter().copied());
+        }
+
+        reachable
+    }
+
+    /// Probability of the enemy being at a given position, proportional to the weight of paths ending there.
+    pub const fn heatmap(&self) -> &HashMap<Coordinate, f64> {
+        &self.heatmap
+    }
+
+    /// The heatmap multiplied by a prior belief about each cell, eg. that the enemy likes to hide
+    /// near islands, and normalized again. Cells missing from `prior` weigh 1.
+    /// Impossible positions stay impossible, and if the prior rules out every position nothing is left.
+    pub fn heatmap_with_prior(&self, prior: &HashMap<Coordinate, f64>) -> HashMap<Coordinate, f64> {
+        let weighted = self
+            .heatmap
+            .iter()
+            .map(|(cell, &probability)| {
+                (*cell, probability * prior.get(cell).copied().unwrap_or(1.0))
+            })
+            .filter(|&(_, weight)| weight > 0.0)
+            .collect::<Vec<_>>();
+        let total: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+
+        weighted
+            .into_iter()
+            .map(|(cell, weight)| (cell, weight / total))
+            .collect()
+    }
+
+    /// Probability of the enemy being in each quadrant, in [`Quadrant::ALL`] order.
+    pub fn quadrant_distribution(&self, map: &Map) -> [(Quadrant, f64); 4] {
+        Quadrant::ALL.map(|quadrant| {
+            let probability = self
+                .heatmap
+                .iter()
+                .filter(|&(&position, _)| map.quadrant_of(position) == Some(quadrant))
+                .map(|(_, probability)| probability)
+                .sum();
+            (quadrant, probability)
+        })
+    }
+
+    /// The quadrant to ask a drone about, ie. the one whose answer is the least predictable.
+    /// `None` if there is nothing left to learn.
+    pub fn best_drone(&self, map: &Map) -> Option<Quadrant> {
+        if self.count() <= 1 {
+            return None;
+        }
+
+        self.quadrant_distribution(map)
+            .into_iter()
+            .min_by(|(_, a), (_, b)| (a - 0.5).abs().total_cmp(&(b - 0.5).abs()))
+            .map(|(quadrant, _)| quadrant)
+    }
+
+    /// Uncertainty about the enemy position in bits, 0 once it is located.
+    pub fn entropy(&self) -> f64 {
+        self.heatmap
+            .values()
+            .filter(|&&probability| probability > 0.0)
+            .map(|&probability| -probability * libm::log2(probability))
+            .sum()
+    }
+
+    /// Open cells inside the bounding box of the positions, in reading order.
+    pub fn search_area(&self, map: &Map) -> Vec<Coordinate> {
+        let positions = self.heatmap.keys();
+        let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = (
+            positions.clone().map(|c| c.x()).min(),
+            positions.clone().map(|c| c.x()).max(),
+            positions.clone().map(|c| c.y()).min(),
+            positions.map(|c| c.y()).max(),
+        ) else {
+            return Vec::new();
+        };
+
+        (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| Coordinate::new(x, y)))
+            .filter(|&c| map.is_open(c))
+            .collect()
+    }
+
+    /// How cornered the enemy is, from 0 when the search area spans the whole map to 1 once it is located.
+    /// 0 when there are no positions.
+    pub fn confinement_score(&self, map: &Map) -> f64 {
+        let area = self.search_area(map).len();
+        if area == 0 {
+            return 0.0;
+        }
+
+        let open = map.open_count();
+        if open <= 1 {
+            return 1.0;
+        }
+        1.0 - (area - 1) as f64 / (open - 1) as f64
+    }
+
+    /// The `k` most probable positions, most probable first. Ties are broken in reading order.
+    pub fn top_positions(&self, k: usize) -> Vec<(Coordinate, f64)> {
+        let mut positions = self
+            .heatmap
+            .iter()
+            .map(|(&position, &probability)| (position, probability))
+            .collect::<Vec<_>>();
+        positions.sort_by(|(a, pa), (b, pb)| pb.total_cmp(pa).then(a.cmp(b)));
+        positions.truncate(k);
+        positions
+    }
+
+    /// Smallest rectangle (top-left, bottom-right) containing all possible positions.
+    pub fn bounding_box(&self) -> Option<(Coordinate, Coordinate)> {
+        let positions = self.positions();
+        let min_x = positions.iter().map(Coordinate::x).min()?;
+        let max_x = positions.iter().map(Coordinate::x).max()?;
+        let min_y = positions.iter().map(Coordinate::y).min()?;
+        let max_y = positions.iter().map(Coordinate::y).max()?;
+
+        Some((Coordinate::new(min_x, min_y), Coordinate::new(max_x, max_y)))
+    }
+
+    pub fn contains(&self, coord: Coordinate) -> bool {
+        self.heatmap.contains_key(&coord)
+    }
+
+    /// Probability that a torpedo exploding at `target` damages the enemy,
+    /// either with a direct hit or by hitting a neighbouring cell.
+    pub fn torpedo_hit_probability(&self, target: Coordinate) -> f64 {
+        self.heatmap
+            .iter()
+            .filter(|&(&position, _)| in_blast(target, position))
+            .map(|(_, probability)| probability)
+            .sum()
+    }
+
+    /// Probability that the enemy is within torpedo range of `cell`, measured as Manhattan distance.
+    pub fn torpedo_threat(&self, cell: Coordinate) -> f64 {
+        self.heatmap
+            .iter()
+            .filter(|&(position, _)| {
+                position.x().abs_diff(cell.x()) + position.y().abs_diff(cell.y()) <= TORPEDO_RANGE
+            })
+            .map(|(_, probability)| probability)
+            .sum()
+    }
+
+    /// Hit probability of a torpedo for every cell of `map` that could damage the enemy.
+    pub fn torpedo_targets(&self, map: &Map) -> HashMap<Coordinate, f64> {
+        (0..map.size())
+            .flat_map(|y| (0..map.size()).map(move |x| Coordinate::new(x, y)))
+            .filter(|&target| map.is_open(target))
+            .map(|target| (target, self.torpedo_hit_probability(target)))
+            .filter(|&(_, probability)| probability > 0.0)
+            .collect()
+    }
+
+    /// The torpedo target most likely to damage the enemy. Ties are broken in reading order.
+    pub fn best_torpedo_target(&self, map: &Map) -> Option<(Coordinate, f64)> {
+        let mut targets = self.torpedo_targets(map).into_iter().collect::<Vec<_>>();
+        targets.sort_by(|(a, pa), (b, pb)| pb.total_cmp(pa).then(a.cmp(b)));
+        targets.into_iter().next()
+    }
+
+    pub fn torpedo_coverage(&self, target: Coordinate) -> TorpedoCoverage {
+        let hit = self
+            .heatmap
+            .keys()
+            .filter(|&&p| in_blast(target, p))
+            .count();
+
+        match hit {
+            0 => TorpedoCoverage::None,
+            _ if hit == self.heatmap.len() => TorpedoCoverage::All,
+            _ => TorpedoCoverage::Some,
+        }
+    }
+}
+
+/// Whether `position` is damaged by an explosion at `target`.
+const fn in_blast(target: Coordinate, position: Coordinate) -> bool {
+    target.x().abs_diff(position.x()) <= 1 && target.y().abs_diff(position.y()) <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::radar::Move;
+
+    #[test]
+    fn possibility_set_queries_agree_on_the_path_ends() {
+        let c = Coordinate::new;
+        let set = PossibilitySet::new(vec![
+            vec![c(0, 0), c(1, 0)],
+            vec![c(3, 3), c(3, 2), c(2, 2)],
+            vec![c(4, 1), c(3, 1), c(2, 1), c(1, 1)],
+            vec![c(2, 3), c(2, 2)],
+        ]);
+
+        assert_eq!(set.paths().len(), 4);
+        assert_eq!(
+            set.positions(),
+            HashSet::from_iter([c(1, 0), c(2, 2), c(1, 1)])
+        );
+        assert_eq!(set.count(), 3);
+        assert!(set.contains(c(2, 2)));
+        assert!(!set.contains(c(0, 0)));
+        assert_eq!(set.bounding_box(), Some((c(1, 0), c(2, 2))));
+
+        // the shared end counts twice
+        let heatmap = set.heatmap();
+        assert_eq!(heatmap.len(), 3);
+        assert_eq!(heatmap[&c(2, 2)], 0.5);
+        assert_eq!(heatmap[&c(1, 0)], 0.25);
+        assert_eq!(set.top_positions(1), [(c(2, 2), 0.5)]);
+    }
+
+    #[test]
+    fn empty_possibility_set_has_no_positions() {
+        let set = PossibilitySet::new(vec![]);
+        assert_eq!(set.count(), 0);
+        assert!(set.positions().is_empty());
+        assert!(set.heatmap().is_empty());
+        assert_eq!(set.bounding_box(), None);
+        assert!(!set.contains(Coordinate::new(0, 0)));
+    }
+
+    #[test]
+    fn analyze_matches_the_radar_queries() {
+        let mut radar = Radar::new(Map::new(6, HashSet::new()));
+        radar
+            .register_move(Move::Directed(Direction::South))
+            .unwrap();
+
+        let set = radar.analyze();
+        assert_eq!(set.positions(), radar.possible_positions());
+        assert_eq!(set.count(), radar.count_possible_positions());
+        assert_eq!(
+            set.bounding_box(),
+            Some((Coordinate::new(0, 1), Coordinate::new(5, 5)))
+        );
+    }
+}
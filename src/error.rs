@@ -0,0 +1,95 @@
+use thiserror::Error;
+
+#[cfg(all(feature = "std", feature = "serde"))]
+use crate::session::SessionError;
+use crate::{
+    maps::MapFileError,
+    radar::{CompareError, IntelError, LogError, MapError, ParseCoordinateError, TraceMoveError},
+};
+
+/// Any error of this crate, for callers that do not need to tell them apart.
+#[derive(Debug, Error)]
+pub enum CaptainSonarError {
+    #[error("Error registering move: {0}")]
+    Move(#[from] TraceMoveError),
+    #[error("Error adding intel: {0}")]
+    Intel(#[from] IntelError),
+    #[error("Invalid map: {0}")]
+    Map(#[from] MapError),
+    #[error("Invalid coordinate: {0}")]
+    Coordinate(#[from] ParseCoordinateError),
+    #[error("Invalid move log: {0}")]
+    Log(#[from] LogError),
+    #[error("Invalid map file: {0}")]
+    MapFile(#[from] MapFileError),
+    #[error("Cannot compare: {0}")]
+    Compare(#[from] CompareError),
+    #[cfg(all(feature = "std", feature = "serde"))]
+    #[error("Session error: {0}")]
+    Session(#[from] SessionError),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, string::ToString};
+
+    use super::*;
+    use crate::radar::Coordinate;
+
+    #[test]
+    fn sub_errors_convert_with_their_message_and_source() {
+        let cases: [(CaptainSonarError, &str, String); 7] = [
+            (
+                TraceMoveError::SelfIntersect.into(),
+                "Error registering move",
+                TraceMoveError::SelfIntersect.to_string(),
+            ),
+            (
+                IntelError::NoSuchStep(4).into(),
+                "Error adding intel",
+                IntelError::NoSuchStep(4).to_string(),
+            ),
+            (
+                MapError::ObstacleOutOfBounds(Coordinate::new(5, 0)).into(),
+                "Invalid map",
+                "Obstacle f1 is out of bounds".to_string(),
+            ),
+            (
+                "zz".parse::<Coordinate>().unwrap_err().into(),
+                "Invalid coordinate",
+                "zz".parse::<Coordinate>().unwrap_err().to_string(),
+            ),
+            (
+                LogError::UnknownEntry("jump".to_string()).into(),
+                "Invalid move log",
+                "Unknown log entry \"jump\"".to_string(),
+            ),
+            (
+                MapFileError::MissingSize.into(),
+                "Invalid map file",
+                MapFileError::MissingSize.to_string(),
+            ),
+            (
+                CompareError::DifferentMaps.into(),
+                "Cannot compare",
+                CompareError::DifferentMaps.to_string(),
+            ),
+        ];
+
+        for (error, context, message) in cases {
+            assert_eq!(error.to_string(), format!("{context}: {message}"));
+            assert_eq!(error.source().unwrap().to_string(), message);
+        }
+    }
+
+    #[cfg(all(feature = "std", feature = "serde"))]
+    #[test]
+    fn session_errors_convert() {
+        let error = CaptainSonarError::from(SessionError::NotFound("game".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "Session error: Nothing saved under the name \"game\""
+        );
+        assert!(error.source().is_some());
+    }
+}
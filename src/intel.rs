@@ -1,6 +1,13 @@
-use std::fmt::Display;
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Display;
+
+use crate::radar::{Coordinate, Direction, Map};
+
+/// Maximum distance a torpedo travels.
+pub const TORPEDO_RANGE: u32 = 4;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Quadrant {
     One,
     Two,
@@ -8,8 +15,12 @@ pub enum Quadrant {
     Four,
 }
 
+impl Quadrant {
+    pub const ALL: [Self; 4] = [Self::One, Self::Two, Self::Three, Self::Four];
+}
+
 impl Display for Quadrant {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let s = match self {
             Self::One => "1",
             Self::Two => "2",
@@ -20,29 +31,239 @@ impl Display for Quadrant {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Orientation of the line splitting the board in halves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Axis {
+    /// splits into a northern and a southern half
+    Horizontal,
+    /// splits into a western and an eastern half
+    Vertical,
+}
+
+/// One of the halves split by an [`Axis`], consistent with [`crate::radar::Map::quadrant_of`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    /// the northern or western half
+    Start,
+    /// the southern or eastern half
+    End,
+}
+
+/// How quadrants are named to the players, see [`crate::radar::Map::quadrant_label`].
+/// Internally they are always [`Quadrant::One`] to [`Quadrant::Four`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuadrantLabel {
+    /// 1 to 4, as in [`Quadrant`]'s `Display`
+    #[default]
+    Numbers,
+    /// A to D
+    Letters,
+    /// NW, NE, SW and SE
+    Compass,
+}
+
+impl QuadrantLabel {
+    pub const fn label(self, quadrant: Quadrant) -> &'static str {
+        match (self, quadrant) {
+            (Self::Numbers, Quadrant::One) => "1",
+            (Self::Numbers, Quadrant::Two) => "2",
+            (Self::Numbers, Quadrant::Three) => "3",
+            (Self::Numbers, Quadrant::Four) => "4",
+            (Self::Letters, Quadrant::One) => "A",
+            (Self::Letters, Quadrant::Two) => "B",
+            (Self::Letters, Quadrant::Three) => "C",
+            (Self::Letters, Quadrant::Four) => "D",
+            (Self::Compass, Quadrant::One) => "NW",
+            (Self::Compass, Quadrant::Two) => "NE",
+            (Self::Compass, Quadrant::Three) => "SW",
+            (Self::Compass, Quadrant::Four) => "SE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InformationPiece {
     Quadrant(Quadrant),
     Column(u32),
     Row(u32),
+    /// Sonar statements phrased as "not in ..."
+    OutsideQuadrant(Quadrant),
+    OutsideColumn(u32),
+    OutsideRow(u32),
+}
+
+impl InformationPiece {
+    /// The piece stating the opposite.
+    pub const fn negated(self) -> Self {
+        match self {
+            Self::Quadrant(q) => Self::OutsideQuadrant(q),
+            Self::Column(c) => Self::OutsideColumn(c),
+            Self::Row(r) => Self::OutsideRow(r),
+            Self::OutsideQuadrant(q) => Self::Quadrant(q),
+            Self::OutsideColumn(c) => Self::Column(c),
+            Self::OutsideRow(r) => Self::Row(r),
+        }
+    }
+
+    pub const fn is_negated(&self) -> bool {
+        matches!(
+            self,
+            Self::OutsideQuadrant(_) | Self::OutsideColumn(_) | Self::OutsideRow(_)
+        )
+    }
 }
 
 impl Display for InformationPiece {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Quadrant(q) => write!(f, "{}", q),
             Self::Column(c) => write!(f, "{}", char::from_u32('a' as u32 + c).unwrap()),
             Self::Row(r) => write!(f, "{}", r + 1),
+            Self::OutsideQuadrant(_) | Self::OutsideColumn(_) | Self::OutsideRow(_) => {
+                write!(f, "not {}", self.negated())
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IntelQuestion {
     /// aka drone
     InQuadrant { quadrant: Quadrant, answer: bool },
+    /// Whether the enemy surfaced in a quadrant on a given turn. Only meaningful for the step
+    /// it describes, so it is meant to be attached with [`crate::radar::Radar::add_intel_at`].
+    SurfacedInQuadrant { quadrant: Quadrant, answer: bool },
+    /// Several negative drone answers at once
+    NotInQuadrants { quadrants: Vec<Quadrant> },
+    /// aka sonar: one of the pieces is true and the other a lie, without saying which,
+    /// so a cell is kept exactly when one of them holds there, ie. a logical XOR
     TruthLie {
         info1: InformationPiece,
         info2: InformationPiece,
     },
+    /// A torpedo fired in a straight line hit somewhere along the line,
+    /// which ends at the map edge, the first obstacle or after `range` cells.
+    LineHit {
+        from: Coordinate,
+        direction: Direction,
+        range: u32,
+    },
+    /// Whether the enemy is at a single cell, when it was deduced by other means
+    Cell { coord: Coordinate, present: bool },
+    /// The enemy is in one half of the board
+    Half { axis: Axis, side: Side },
+    /// The enemy is in the same row as our own submarine, given by its row
+    SharesRowWith(u32),
+    /// The enemy is in the same column as our own submarine, given by its column
+    SharesColumnWith(u32),
+}
+
+/// Names the kind of the piece alongside its value, eg. "row 3" or "not column e".
+fn describe_piece(piece: &InformationPiece, map: &Map) -> String {
+    let positive = if piece.is_negated() {
+        piece.negated()
+    } else {
+        *piece
+    };
+    let value = match positive {
+        InformationPiece::Quadrant(q) => format!("quadrant {}", map.quadrant_label().label(q)),
+        InformationPiece::Column(_) => format!("column {}", positive),
+        _ => format!("row {}", positive),
+    };
+    if piece.is_negated() {
+        format!("not {}", value)
+    } else {
+        value
+    }
+}
+
+impl IntelQuestion {
+    /// Whether the intel keeps constraining its step once the enemy surfaced later on.
+    /// Drones and sonars are persistent, point-in-time facts such as a cell, a line or
+    /// where the enemy surfaced are step-local and forgotten at the next surface.
+    pub const fn is_persistent(&self) -> bool {
+        match self {
+            Self::InQuadrant { .. }
+            | Self::NotInQuadrants { .. }
+            | Self::TruthLie { .. }
+            | Self::Half { .. } => true,
+            Self::SurfacedInQuadrant { .. }
+            | Self::LineHit { .. }
+            | Self::Cell { .. }
+            | Self::SharesRowWith(_)
+            | Self::SharesColumnWith(_) => false,
+        }
+    }
+
+    /// Human readable phrasing of the intel, eg. "Drone: in quadrant 2 — yes".
+    pub fn describe(&self, map: &Map) -> String {
+        let yes_no = |answer: bool| if answer { "yes" } else { "no" };
+        let label = |quadrant: &Quadrant| map.quadrant_label().label(*quadrant);
+        match self {
+            Self::InQuadrant { quadrant, answer } => {
+                format!(
+                    "Drone: in quadrant {} — {}",
+                    label(quadrant),
+                    yes_no(*answer)
+                )
+            }
+            Self::SurfacedInQuadrant { quadrant, answer } => {
+                format!(
+                    "Surfaced: in quadrant {} — {}",
+                    label(quadrant),
+                    yes_no(*answer)
+                )
+            }
+            Self::NotInQuadrants { quadrants } => {
+                let quadrants: Vec<_> = quadrants.iter().map(label).collect();
+                format!("Drone: not in quadrants {}", quadrants.join(", "))
+            }
+            Self::TruthLie { info1, info2 } => {
+                format!(
+                    "Sonar truth/lie: {} vs {}",
+                    describe_piece(info1, map),
+                    describe_piece(info2, map)
+                )
+            }
+            &Self::LineHit {
+                from,
+                direction,
+                range,
+            } => {
+                let heading = match direction {
+                    Direction::North => "north",
+                    Direction::East => "east",
+                    Direction::South => "south",
+                    Direction::West => "west",
+                };
+                match map.ray(from, direction, range).last() {
+                    Some(to) => format!("Torpedo: hit on the line {} {} to {}", from, heading, to),
+                    None => format!("Torpedo: hit on the line {} {}", from, heading),
+                }
+            }
+            Self::Cell { coord, present } => {
+                format!("Cell: at {} — {}", coord, yes_no(*present))
+            }
+            Self::Half { axis, side } => {
+                let half = match (axis, side) {
+                    (Axis::Horizontal, Side::Start) => "north",
+                    (Axis::Horizontal, Side::End) => "south",
+                    (Axis::Vertical, Side::Start) => "west",
+                    (Axis::Vertical, Side::End) => "east",
+                };
+                format!("Half: in the {} half", half)
+            }
+            &Self::SharesRowWith(row) => {
+                format!("Shares our row {}", InformationPiece::Row(row))
+            }
+            &Self::SharesColumnWith(column) => {
+                format!("Shares our column {}", InformationPiece::Column(column))
+            }
+        }
+    }
 }
@@ -1,2 +1,28 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(not(any(feature = "std", feature = "hashbrown")))]
+compile_error!("building without `std` requires the `hashbrown` feature");
+
+pub mod action;
+pub mod analysis;
+pub mod error;
 pub mod intel;
+pub mod maps;
 pub mod radar;
+#[cfg(feature = "image")]
+pub mod render;
+pub mod scenario;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub mod session;
+
+pub use error::CaptainSonarError;
+
+/// Hash collections from `std`, or from `hashbrown` when building without `std`.
+pub mod collections {
+    #[cfg(not(feature = "std"))]
+    pub use hashbrown::{HashMap, HashSet};
+    #[cfg(feature = "std")]
+    pub use std::collections::{HashMap, HashSet};
+}
@@ -1,52 +1,552 @@
 use captain_sonar::{
-    intel::{InformationPiece, IntelQuestion, Quadrant},
+    action::Action,
+    analysis::{EmptyReason, PossibilitySet, TorpedoCoverage},
+    intel::{Axis, InformationPiece, IntelQuestion, Quadrant, QuadrantLabel, Side, TORPEDO_RANGE},
     radar::*,
+    session::{SessionError, SessionStore},
+    CaptainSonarError,
 };
 use thiserror::Error;
 
-use std::{collections::HashSet, fmt::Display, io};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use ratatui::{buffer::Buffer, layout::Rect, text::Text, widgets::Widget, DefaultTerminal, Frame};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    text::Text,
+    widgets::{Block, Clear, Gauge, Paragraph, Widget},
+    DefaultTerminal, Frame,
+};
 
-fn radar_to_string(radar: &Radar, path: &[DecoratedCoordinate]) -> String {
-    let mut result = String::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GridStyle {
+    /// cells separated by spaces and blank rows
+    Spacious,
+    /// one character per cell, for small terminals
+    Compact,
+}
+
+impl GridStyle {
+    /// lines below the grid reserved for the status
+    const STATUS_HEIGHT: u32 = 4;
+
+    /// picks the spacious style if it fits into the area
+    fn for_area(area: Rect, map_size: u32) -> Self {
+        let width = map_size * 3 + 3;
+        let height = map_size * 2 + 1 + Self::STATUS_HEIGHT;
+
+        if u32::from(area.width) < width || u32::from(area.height) < height {
+            Self::Compact
+        } else {
+            Self::Spacious
+        }
+    }
+
+    /// most cells per side that fit into the area in the compact style
+    fn max_cells(area: Rect) -> u32 {
+        let width = u32::from(area.width).saturating_sub(3);
+        let height = u32::from(area.height).saturating_sub(3 + Self::STATUS_HEIGHT);
+        width.min(height).max(1)
+    }
+
+    /// width of `cells` cells between two borders, `wide` cells hold two characters
+    const fn span(self, cells: u32, wide: bool) -> usize {
+        match self {
+            Self::Compact if !wide => cells as usize,
+            Self::Spacious | Self::Compact => cells as usize * 3,
+        }
+    }
+}
+
+/// Characters used to draw the border and quadrant separators of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Unicode,
+    /// for terminals that cannot render box-drawing characters
+    Ascii,
+}
+
+impl Charset {
+    const fn toggled(self) -> Self {
+        match self {
+            Self::Unicode => Self::Ascii,
+            Self::Ascii => Self::Unicode,
+        }
+    }
+
+    const fn horizontal(self) -> char {
+        match self {
+            Self::Unicode => '─',
+            Self::Ascii => '-',
+        }
+    }
+
+    const fn vertical(self) -> char {
+        match self {
+            Self::Unicode => '│',
+            Self::Ascii => '|',
+        }
+    }
+
+    /// left, crossing and right characters of the top, middle and bottom lines
+    const fn joints(self, line: usize) -> [char; 3] {
+        match (self, line) {
+            (Self::Ascii, _) => ['+', '+', '+'],
+            (Self::Unicode, 0) => ['┌', '┬', '┐'],
+            (Self::Unicode, 1) => ['├', '┼', '┤'],
+            (Self::Unicode, _) => ['└', '┴', '┘'],
+        }
+    }
+}
+
+/// glyphs of a single path and the blast area of its mines.
+/// With `ghost`, the trail wiped by the latest surface is kept as a faint wake
+fn path_glyphs<'a>(
+    map: &'a Map,
+    path: &[DecoratedCoordinate],
+    ghost: bool,
+) -> impl Fn(Coordinate) -> char + 'a {
     let mines = path
         .iter()
         .filter_map(|c| if c.has_mine() { Some(c.coord()) } else { None })
-        .flat_map(|c| Coordinate::neighbours(&c))
+        .flat_map(|c| map.neighbours_8(c))
+        .collect::<HashSet<_>>();
+    let surfaced = path
+        .iter()
+        .rposition(DecoratedCoordinate::surfaced)
+        .unwrap_or(0);
+    let wake = if ghost {
+        path[..surfaced].iter().map(|c| c.coord()).collect()
+    } else {
+        HashSet::new()
+    };
+    let path = path[surfaced..]
+        .iter()
+        .map(|c| c.coord())
         .collect::<HashSet<_>>();
-    let path = path.iter().map(|c| c.coord()).collect::<HashSet<_>>();
-
-    for y in 0..radar.map().size() {
-        for x in 0..radar.map().size() {
-            let coordinate = Coordinate::new(x, y);
-            if radar.map().obstacles().contains(&coordinate) {
-                result.push('#');
-            } else if path.contains(&coordinate) {
-                result.push('*');
-            } else if mines.contains(&coordinate) {
-                result.push('x');
-            } else {
-                result.push('.');
-            }
 
-            if x != radar.map().size() - 1 {
-                result.push_str("  ");
+    move |coordinate| {
+        if map.obstacles().contains(&coordinate) {
+            '#'
+        } else if path.contains(&coordinate) {
+            '*'
+        } else if wake.contains(&coordinate) {
+            '~'
+        } else if mines.contains(&coordinate) {
+            'x'
+        } else {
+            '.'
+        }
+    }
+}
+
+/// two-character percentage of the enemy being at a cell, blank if it cannot be there
+fn percentage(probability: f64) -> String {
+    if probability <= 0.0 {
+        String::new()
+    } else if probability >= 1.0 {
+        "@".to_string()
+    } else {
+        match (probability * 100.0).round() as u32 {
+            0 => "<1".to_string(),
+            p => p.min(99).to_string(),
+        }
+    }
+}
+
+/// `cell: p%` for each of the given positions in order, rounded so that the percentages add up to 100
+fn confidence_lines(positions: &[(Coordinate, f64)]) -> Vec<String> {
+    let total = positions.iter().map(|(_, p)| p).sum::<f64>();
+    if total <= 0.0 {
+        return vec![];
+    }
+
+    let exact = positions
+        .iter()
+        .map(|(_, p)| p / total * 100.0)
+        .collect::<Vec<_>>();
+    let mut rounded = exact.iter().map(|p| p.floor() as u32).collect::<Vec<_>>();
+
+    // the largest remainders get the points lost to rounding down, earlier cells win ties
+    let mut by_remainder = (0..exact.len()).collect::<Vec<_>>();
+    by_remainder
+        .sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+    let missing = 100 - rounded.iter().sum::<u32>().min(100);
+    for &i in by_remainder.iter().take(missing as usize) {
+        rounded[i] += 1;
+    }
+
+    positions
+        .iter()
+        .zip(rounded)
+        .map(|((cell, _), percent)| format!("{cell}: {percent}%"))
+        .collect()
+}
+
+/// percentages of the enemy being at each cell
+fn number_glyphs<'a>(
+    map: &'a Map,
+    heatmap: &'a HashMap<Coordinate, f64>,
+) -> impl Fn(Coordinate) -> String + 'a {
+    move |coordinate| {
+        if map.obstacles().contains(&coordinate) {
+            "#".to_string()
+        } else {
+            percentage(heatmap.get(&coordinate).copied().unwrap_or(0.0))
+        }
+    }
+}
+
+/// glyphs marking only the given cells, the rest is greyed out
+fn highlight_glyphs<'a>(
+    map: &'a Map,
+    cells: &'a HashSet<Coordinate>,
+    charset: Charset,
+) -> impl Fn(Coordinate) -> char + 'a {
+    move |coordinate| match charset {
+        _ if map.obstacles().contains(&coordinate) => '#',
+        Charset::Unicode if cells.contains(&coordinate) => '●',
+        Charset::Unicode => '·',
+        Charset::Ascii if cells.contains(&coordinate) => 'o',
+        Charset::Ascii => ' ',
+    }
+}
+
+/// The rectangle of cells that is drawn, the whole map unless following the possible positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Viewport {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Viewport {
+    const fn full(size: u32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: size,
+            height: size,
+        }
+    }
+
+    /// the smallest view showing `cells` with `margin` cells around them, kept on the map.
+    /// If that is more than `max` cells per side, the view is centred on the cells instead
+    fn around(
+        cells: impl Iterator<Item = Coordinate> + Clone,
+        size: u32,
+        margin: u32,
+        max: u32,
+    ) -> Self {
+        // start and length along one axis
+        let axis = |values: &mut dyn Iterator<Item = u32>| {
+            let (mut lo, mut hi) = (u32::MAX, 0);
+            for v in values {
+                lo = lo.min(v);
+                hi = hi.max(v);
+            }
+            let (lo, hi) = (lo.saturating_sub(margin), (hi + margin).min(size - 1));
+            let len = hi - lo + 1;
+            if len <= max {
+                return (lo, len);
             }
+            let centre = (lo + hi) / 2;
+            (centre.saturating_sub(max / 2).min(size - max), max)
+        };
+
+        if cells.clone().next().is_none() {
+            return Self::full(size);
+        }
+        let (x, width) = axis(&mut cells.clone().map(|c| c.x()));
+        let (y, height) = axis(&mut cells.map(|c| c.y()));
+        Self {
+            x,
+            y,
+            width,
+            height,
         }
-        result.push('\n');
-        if y != radar.map().size() - 1 {
-            for _ in 0..radar.map().size() * 2 - 1 {
-                result.push(' ');
+    }
+
+    /// the map cell shown at a column and row of the view
+    const fn cell_at(&self, column: u32, row: u32) -> Coordinate {
+        Coordinate::new(self.x + column, self.y + row)
+    }
+}
+
+/// draws the cells of the view with a glyph per cell, `wide` glyphs may take two characters
+fn radar_to_string<G: Display>(
+    map: &Map,
+    view: Viewport,
+    style: GridStyle,
+    charset: Charset,
+    wide: bool,
+    glyph: impl Fn(Coordinate) -> G,
+) -> String {
+    let mut result = String::new();
+
+    // the quadrant separators are only drawn if they fall inside the view
+    let half = map.size() / 2;
+    let split_x = (view.x < half && half < view.x + view.width).then_some(half - view.x);
+    let split_y = (view.y < half && half < view.y + view.height).then_some(half - view.y);
+    let (left, right) = match split_x {
+        Some(split) => (
+            style.span(split, wide),
+            Some(style.span(view.width - split, wide)),
+        ),
+        None => (style.span(view.width, wide), None),
+    };
+    // joins the left and the optional right part of a line with the given delimiters
+    let line = |[l, m, r]: [char; 3], left: String, right: Option<String>| match right {
+        Some(right) => format!("{l}{left}{m}{right}{r}\n"),
+        None => format!("{l}{left}{r}\n"),
+    };
+
+    // a horizontal line spanning the grid, `line` selects the top, middle or bottom joints
+    let rule = |joints: usize, fill: char| {
+        let fill = fill.to_string();
+        line(
+            charset.joints(joints),
+            fill.repeat(left),
+            right.map(|right| fill.repeat(right)),
+        )
+    };
+    let v = charset.vertical();
+    let blank = line(
+        [v, v, v],
+        " ".repeat(left),
+        right.map(|right| " ".repeat(right)),
+    );
+    let cells = |row: u32, columns: std::ops::Range<u32>| {
+        columns
+            .map(|column| {
+                let glyph = glyph(view.cell_at(column, row));
+                match style {
+                    GridStyle::Compact if !wide => glyph.to_string(),
+                    // right aligned so that numbers line up
+                    GridStyle::Spacious | GridStyle::Compact => format!("{glyph:>2} "),
+                }
+            })
+            .collect::<String>()
+    };
+
+    result.push_str(&rule(0, charset.horizontal()));
+    for row in 0..view.height {
+        if row != 0 {
+            if Some(row) == split_y {
+                result.push_str(&rule(1, charset.horizontal()));
+            } else if style == GridStyle::Spacious {
+                result.push_str(&blank);
             }
-            result.push('\n');
         }
+
+        result.push_str(&match split_x {
+            Some(split) => line(
+                [v, v, v],
+                cells(row, 0..split),
+                Some(cells(row, split..view.width)),
+            ),
+            None => line([v, v, v], cells(row, 0..view.width), None),
+        });
     }
+    result.push_str(&rule(2, charset.horizontal()));
 
     result
 }
 
+/// Compass-style summary of a displacement, eg. `↗ 3E 2N`.
+fn compass(offset: Offset) -> String {
+    let arrow = match (offset.x().signum(), offset.y().signum()) {
+        (0, 0) => return "· no drift".to_string(),
+        (0, -1) => '↑',
+        (1, -1) => '↗',
+        (1, 0) => '→',
+        (1, 1) => '↘',
+        (0, 1) => '↓',
+        (-1, 1) => '↙',
+        (-1, 0) => '←',
+        _ => '↖',
+    };
+
+    let mut parts = vec![arrow.to_string()];
+    match offset.x().signum() {
+        1 => parts.push(format!("{}E", offset.x())),
+        -1 => parts.push(format!("{}W", -offset.x())),
+        _ => (),
+    }
+    match offset.y().signum() {
+        1 => parts.push(format!("{}S", offset.y())),
+        -1 => parts.push(format!("{}N", -offset.y())),
+        _ => (),
+    }
+
+    parts.join(" ")
+}
+
+/// `~/.captain_sonar/sessions`, or relative to the working directory if there is no home
+fn default_session_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".captain_sonar")
+        .join("sessions")
+}
+
+/// registers `count` moves in `direction`, stopping at the first illegal one
+fn register_burst(radar: &mut Radar, direction: Direction, count: usize) -> Result<(), AppError> {
+    if count == 0 {
+        return Err(AppError::EmptyBurst);
+    }
+    // a straight line can never be longer than the board
+    let longest = count.min(radar.map().size() as usize);
+    match radar.register_moves(&vec![Move::Directed(direction); longest]) {
+        Ok(done) if done < longest => Err(AppError::Burst {
+            done,
+            requested: count,
+        }),
+        Ok(done) if done < count => Err(AppError::BurstClamped {
+            done,
+            requested: count,
+        }),
+        Ok(_) => Ok(()),
+        Err(e) => Err(AppError::Sonar(e.into())),
+    }
+}
+
+/// applies an action to the radar without a terminal, actions that only affect the view are ignored
+fn apply_headless(radar: &mut Radar, action: &Action) -> Result<(), AppError> {
+    let sonar = |e: CaptainSonarError| AppError::Sonar(e);
+    match action {
+        &Action::Move(direction) => radar
+            .register_move(Move::Directed(direction))
+            .map_err(|e| sonar(e.into())),
+        &Action::Burst { direction, count } => register_burst(radar, direction, count),
+        Action::Silence => radar.register_move(Move::Dash).map_err(|e| sonar(e.into())),
+        Action::Surface => radar
+            .register_move(Move::Surface)
+            .map_err(|e| sonar(e.into())),
+        Action::Mine => {
+            radar.plant_mine();
+            Ok(())
+        }
+        Action::Intel(intel) => radar
+            .try_add_intel(intel.clone())
+            .map_err(|e| sonar(e.into())),
+        Action::Undo => {
+            radar.undo_trace();
+            Ok(())
+        }
+        Action::UndoToLastIntel => {
+            radar.undo_to_last_intel();
+            Ok(())
+        }
+        Action::UndoToLastSurface => {
+            radar.undo_to_last_surface();
+            Ok(())
+        }
+        Action::ClearIntel => {
+            radar.clear_intel();
+            Ok(())
+        }
+        &Action::CommitSilence {
+            index,
+            direction,
+            distance,
+        } => radar
+            .commit_silence(index, direction, distance)
+            .map_err(|e| sonar(e.into())),
+        Action::NextPath
+        | Action::ToggleHelp
+        | Action::ToggleTop(_)
+        | Action::ToggleCone
+        | Action::AdjustCone(_)
+        | Action::ToggleNumbers
+        | Action::ToggleGhost
+        | Action::ToggleCharset
+        | Action::ToggleFollow
+        | Action::Quit => Ok(()),
+    }
+}
+
+/// Reads actions from stdin and prints one JSON line per applied action for dashboards to tail.
+/// Lines starting with `{` are JSON encoded actions, any other line is a move log.
+/// A line that cannot be read as either is reported with its error and skipped.
+fn run_jsonl(mut radar: Radar) -> io::Result<()> {
+    const TOP: usize = 5;
+
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lines() {
+        let line = line?;
+        let actions = if line.trim_start().starts_with('{') {
+            serde_json::from_str::<Action>(&line)
+                .map(|action| vec![action])
+                .map_err(|e| e.to_string())
+        } else {
+            parse_log(&line)
+                .map(|elements| {
+                    elements
+                        .into_iter()
+                        .map(|element| match element {
+                            TraceElement::Move(Move::Directed(direction)) => {
+                                Action::Move(direction)
+                            }
+                            TraceElement::Move(Move::Dash | Move::DashExcluding(_)) => {
+                                Action::Silence
+                            }
+                            TraceElement::Move(Move::Surface) => Action::Surface,
+                            TraceElement::Mine => Action::Mine,
+                            TraceElement::Intel(intel) => Action::Intel(intel),
+                        })
+                        .collect()
+                })
+                .map_err(|e| e.to_string())
+        };
+        let actions = match actions {
+            Ok(actions) => actions,
+            Err(error) => {
+                let update = serde_json::json!({ "line": line, "error": error });
+                writeln!(stdout, "{update}")?;
+                stdout.flush()?;
+                continue;
+            }
+        };
+
+        for action in actions {
+            let error = apply_headless(&mut radar, &action).err();
+            let possibilities = radar.analyze();
+            let top = possibilities
+                .top_positions(TOP)
+                .into_iter()
+                .map(|(cell, probability)| (cell.to_string(), probability))
+                .collect::<Vec<_>>();
+            let update = serde_json::json!({
+                "action": action,
+                "error": error.map(|e| e.to_string()),
+                "positions": possibilities.count(),
+                "top": top,
+                "entropy": possibilities.entropy(),
+            });
+            writeln!(stdout, "{update}")?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
 fn main() -> io::Result<()> {
     let map = Map::new(
         10,
@@ -64,16 +564,54 @@ fn main() -> io::Result<()> {
 
     let radar = Radar::new(map);
 
+    let mut app = App::new(radar);
+    let mut jsonl = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ascii" => app.charset = Charset::Ascii,
+            "--jsonl" => jsonl = true,
+            "--poll-ms" => {
+                app.poll_interval = args
+                    .next()
+                    .and_then(|ms| ms.parse().ok())
+                    .map(Duration::from_millis)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "--poll-ms expects a number of milliseconds",
+                        )
+                    })?;
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown argument {arg:?}"),
+                ))
+            }
+        }
+    }
+
+    if jsonl {
+        return run_jsonl(app.radar);
+    }
+
     let mut terminal = ratatui::init();
-    let app_result = App::new(radar).run(&mut terminal);
+    let app_result = app.run(&mut terminal);
     ratatui::restore();
     app_result
 }
 
 #[derive(Debug, Error)]
 enum AppError {
-    #[error("Error registering move: {0}")]
-    Move(TraceMoveError),
+    #[error(transparent)]
+    Sonar(#[from] CaptainSonarError),
+    #[error("Stopped after {done} of {requested} moves, the next one would intersect the path")]
+    Burst { done: usize, requested: usize },
+    #[error("Stopped after {done} of {requested} moves, a straight line cannot be longer than the board")]
+    BurstClamped { done: usize, requested: usize },
+    #[error("A repeated move needs a count of at least 1")]
+    EmptyBurst,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -86,9 +624,9 @@ enum PickTruthLieKind {
 impl From<InformationPiece> for PickTruthLieKind {
     fn from(value: InformationPiece) -> Self {
         match value {
-            InformationPiece::Quadrant(_) => Self::Quadrant,
-            InformationPiece::Row(_) => Self::Row,
-            InformationPiece::Column(_) => Self::Column,
+            InformationPiece::Quadrant(_) | InformationPiece::OutsideQuadrant(_) => Self::Quadrant,
+            InformationPiece::Row(_) | InformationPiece::OutsideRow(_) => Self::Row,
+            InformationPiece::Column(_) | InformationPiece::OutsideColumn(_) => Self::Column,
         }
     }
 }
@@ -125,14 +663,10 @@ impl PickTruthLieProgress {
             Self::Info1Kind { kind: _, data } if data.is_empty() => Self::None,
             Self::Info1Kind { kind, data } => Self::Info1Kind {
                 kind: *kind,
-                data: data[..data.len() - 1].to_string(),
+                data: without_last(data),
             },
             Self::Info1Information(piece) => Self::Info1Kind {
-                kind: match piece {
-                    InformationPiece::Quadrant(_) => PickTruthLieKind::Quadrant,
-                    InformationPiece::Row(_) => PickTruthLieKind::Row,
-                    InformationPiece::Column(_) => PickTruthLieKind::Column,
-                },
+                kind: PickTruthLieKind::from(*piece),
                 data: String::new(),
             },
             Self::Info2Kind { info1, data, .. } if data.is_empty() => {
@@ -141,7 +675,93 @@ impl PickTruthLieProgress {
             Self::Info2Kind { info1, kind, data } => Self::Info2Kind {
                 info1: *info1,
                 kind: *kind,
-                data: data[..data.len() - 1].to_string(),
+                data: without_last(data),
+            },
+        })
+    }
+}
+
+/// `input` without its last character, for backspace in text prompts
+fn without_last(input: &str) -> String {
+    let mut input = input.to_string();
+    input.pop();
+    input
+}
+
+/// characters accepted when typing a session or macro name
+const fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+#[derive(Debug, Clone)]
+enum TorpedoProgress {
+    Target {
+        input: String,
+        hint: Option<String>,
+    },
+    Aimed {
+        target: Coordinate,
+        coverage: TorpedoCoverage,
+        probability: f64,
+        best: Option<(Coordinate, f64)>,
+    },
+}
+
+impl TorpedoProgress {
+    fn previous(&self) -> Option<Self> {
+        Some(match self {
+            Self::Target { input, .. } if input.is_empty() => return None,
+            Self::Target { input, .. } => Self::Target {
+                input: without_last(input),
+                hint: None,
+            },
+            Self::Aimed { .. } => Self::Target {
+                input: String::new(),
+                hint: None,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum LineHitProgress {
+    From { input: String, hint: Option<String> },
+    Direction { from: Coordinate },
+}
+
+impl LineHitProgress {
+    fn previous(&self) -> Option<Self> {
+        Some(match self {
+            Self::From { input, .. } if input.is_empty() => return None,
+            Self::From { input, .. } => Self::From {
+                input: without_last(input),
+                hint: None,
+            },
+            Self::Direction { from } => Self::From {
+                input: from.to_string(),
+                hint: None,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum CellIntelProgress {
+    Cell { input: String, hint: Option<String> },
+    Answer { coord: Coordinate },
+}
+
+impl CellIntelProgress {
+    fn previous(&self) -> Option<Self> {
+        Some(match self {
+            Self::Cell { input, .. } if input.is_empty() => return None,
+            Self::Cell { input, .. } => Self::Cell {
+                input: without_last(input),
+                hint: None,
+            },
+            Self::Answer { coord } => Self::Cell {
+                input: coord.to_string(),
+                hint: None,
             },
         })
     }
@@ -149,38 +769,419 @@ impl PickTruthLieProgress {
 
 #[derive(Debug, Clone)]
 enum Submenu {
-    IntelPickQuadrant { quadrant: Option<Quadrant> },
+    IntelPickQuadrant {
+        quadrant: Option<Quadrant>,
+    },
+    ExcludeQuadrants {
+        quadrants: Vec<Quadrant>,
+    },
     IntelPickTruthLie(PickTruthLieProgress),
+    SaveSession {
+        name: String,
+    },
+    LoadSession {
+        /// session names with their possible position counts, once counted
+        sessions: Vec<(String, Option<usize>)>,
+        selected: usize,
+    },
+    Torpedo(TorpedoProgress),
+    LineHit(LineHitProgress),
+    CellIntel(CellIntelProgress),
+    /// typing the cell whose obstacle is toggled
+    EditObstacle {
+        input: String,
+        hint: Option<String>,
+    },
+    SaveMacro {
+        name: String,
+        actions: Vec<Action>,
+    },
+    PickMacro {
+        macros: Vec<String>,
+        selected: usize,
+    },
+    ConfirmClearIntel,
+    PickHalf,
 }
 
 impl Submenu {
     fn previous(&self) -> Option<Self> {
         Some(match self {
-            Self::IntelPickQuadrant { quadrant: None } => return None,
+            Self::IntelPickQuadrant { quadrant: None }
+            | Self::LoadSession { .. }
+            | Self::PickMacro { .. }
+            | Self::ConfirmClearIntel
+            | Self::PickHalf => return None,
             Self::IntelPickQuadrant { quadrant: Some(_) } => {
                 Self::IntelPickQuadrant { quadrant: None }
             }
+            Self::ExcludeQuadrants { quadrants } if quadrants.is_empty() => return None,
+            Self::ExcludeQuadrants { quadrants } => Self::ExcludeQuadrants {
+                quadrants: quadrants[..quadrants.len() - 1].to_vec(),
+            },
             Self::IntelPickTruthLie(progress) => Self::IntelPickTruthLie(progress.previous()?),
+            Self::Torpedo(progress) => Self::Torpedo(progress.previous()?),
+            Self::LineHit(progress) => Self::LineHit(progress.previous()?),
+            Self::CellIntel(progress) => Self::CellIntel(progress.previous()?),
+            Self::EditObstacle { input, .. } if input.is_empty() => return None,
+            Self::EditObstacle { input, .. } => Self::EditObstacle {
+                input: without_last(input),
+                hint: None,
+            },
+            Self::SaveSession { name } if name.is_empty() => return None,
+            Self::SaveSession { name } => Self::SaveSession {
+                name: without_last(name),
+            },
+            Self::SaveMacro { name, .. } if name.is_empty() => return None,
+            Self::SaveMacro { name, actions } => Self::SaveMacro {
+                name: without_last(name),
+                actions: actions.clone(),
+            },
         })
     }
 }
 
+/// Obstacle toggles made in the editor, undone and redone separately from the trace.
+/// A toggle is its own inverse, so only the toggled cells are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct MapEdits {
+    done: Vec<Coordinate>,
+    undone: Vec<Coordinate>,
+}
+
+impl MapEdits {
+    /// a new toggle, which drops the toggles that could have been redone
+    fn record(&mut self, cell: Coordinate) {
+        self.done.push(cell);
+        self.undone.clear();
+    }
+
+    /// the cell to toggle to undo the latest edit
+    fn undo(&mut self) -> Option<Coordinate> {
+        let cell = self.done.pop()?;
+        self.undone.push(cell);
+        Some(cell)
+    }
+
+    /// the cell to toggle to redo the latest undone edit
+    fn redo(&mut self) -> Option<Coordinate> {
+        let cell = self.undone.pop()?;
+        self.done.push(cell);
+        Some(cell)
+    }
+}
+
+/// Steps through the trace, showing the possibilities after each prefix of it.
+/// The timing is fed in through [`Replay::tick`] so it does not depend on a real clock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Replay {
+    /// length of the shown trace prefix
+    step: usize,
+    /// length of the whole trace
+    len: usize,
+    playing: bool,
+    /// time between two steps while playing
+    interval: Duration,
+    /// time accumulated towards the next step
+    elapsed: Duration,
+}
+
+impl Replay {
+    const DEFAULT_INTERVAL: Duration = Duration::from_millis(800);
+    const MIN_INTERVAL: Duration = Duration::from_millis(100);
+    const MAX_INTERVAL: Duration = Duration::from_millis(6400);
+
+    const fn new(len: usize) -> Self {
+        Self {
+            step: 0,
+            len,
+            playing: false,
+            interval: Self::DEFAULT_INTERVAL,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// moves the step by `delta`, clamped to the trace
+    fn step_by(&mut self, delta: isize) {
+        self.step = self.step.saturating_add_signed(delta).min(self.len);
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// playing at the end starts over
+    const fn toggle_playing(&mut self) {
+        self.playing = !self.playing;
+        if self.playing && self.step == self.len {
+            self.step = 0;
+        }
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// halves (`faster`) or doubles the interval between steps
+    fn change_speed(&mut self, faster: bool) {
+        self.interval = if faster {
+            (self.interval / 2).max(Self::MIN_INTERVAL)
+        } else {
+            (self.interval * 2).min(Self::MAX_INTERVAL)
+        };
+    }
+
+    /// advances by as many steps as fit into the time passed, pausing at the final step.
+    /// Returns whether the step changed.
+    fn tick(&mut self, passed: Duration) -> bool {
+        if !self.playing {
+            return false;
+        }
+
+        let before = self.step;
+        self.elapsed += passed;
+        while self.elapsed >= self.interval && self.step < self.len {
+            self.elapsed -= self.interval;
+            self.step += 1;
+        }
+        if self.step == self.len {
+            self.playing = false;
+            self.elapsed = Duration::ZERO;
+        }
+
+        self.step != before
+    }
+
+    /// how long until the next step is due, if playing
+    fn until_next(&self) -> Option<Duration> {
+        self.playing
+            .then(|| self.interval.saturating_sub(self.elapsed))
+    }
+}
+
+#[derive(Debug)]
+struct PathRequest {
+    generation: u64,
+    radar: Radar,
+}
+
+#[derive(Debug)]
+struct PathResult {
+    generation: u64,
+    paths: Vec<Vec<DecoratedCoordinate>>,
+    possibilities: PossibilitySet,
+    /// only diagnosed when there are no paths
+    empty_reason: Option<EmptyReason>,
+    unchanged_for: usize,
+    /// only looked for when there are paths
+    settled_silence: Option<(usize, Direction, u8)>,
+}
+
+/// Computes possible paths on a background thread so that input stays responsive.
+/// Every request bumps the generation, results of older generations are discarded.
+#[derive(Debug)]
+struct PathWorker {
+    requests: Sender<PathRequest>,
+    results: Receiver<PathResult>,
+    generation: u64,
+    computing: bool,
+    /// fraction of the current computation that is done, as the bits of an `f32`
+    progress: Arc<AtomicU32>,
+}
+
+impl PathWorker {
+    fn spawn() -> Self {
+        let (request_tx, request_rx) = mpsc::channel::<PathRequest>();
+        let (result_tx, result_rx) = mpsc::channel();
+        let progress = Arc::new(AtomicU32::new(0));
+        let worker_progress = Arc::clone(&progress);
+
+        thread::spawn(move || {
+            // the last radar with its positions and unchanged count, to extend the count by one step
+            let mut previous: Option<(Radar, HashSet<Coordinate>, usize)> = None;
+            while let Ok(mut request) = request_rx.recv() {
+                // only the newest snapshot is worth computing
+                while let Ok(newer) = request_rx.try_recv() {
+                    request = newer;
+                }
+
+                let weighted: Vec<_> = request
+                    .radar
+                    .get_weighted_paths_with_progress(|done| {
+                        worker_progress.store(done.to_bits(), Ordering::Relaxed);
+                    })
+                    .collect();
+                let possibilities = PossibilitySet::from_weighted_decorated(&weighted);
+                let paths: Vec<_> = weighted.into_iter().map(|(_, path)| path).collect();
+                let positions = possibilities.positions();
+                let unchanged_for = previous
+                    .as_ref()
+                    .and_then(|(radar, before, unchanged)| {
+                        request.radar.positions_unchanged_after(
+                            radar,
+                            *unchanged,
+                            *before == positions,
+                        )
+                    })
+                    .unwrap_or_else(|| request.radar.positions_unchanged_for());
+                previous = Some((request.radar.clone(), positions, unchanged_for));
+                let result = PathResult {
+                    generation: request.generation,
+                    possibilities,
+                    empty_reason: if paths.is_empty() {
+                        request.radar.diagnose_empty()
+                    } else {
+                        None
+                    },
+                    unchanged_for,
+                    settled_silence: if paths.is_empty() {
+                        None
+                    } else {
+                        request.radar.unambiguous_silence()
+                    },
+                    paths,
+                };
+                if result_tx.send(result).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            requests: request_tx,
+            results: result_rx,
+            generation: 0,
+            computing: false,
+            progress,
+        }
+    }
+
+    fn request(&mut self, radar: &Radar) {
+        self.generation += 1;
+        self.computing = true;
+        self.progress.store(0.0f32.to_bits(), Ordering::Relaxed);
+        // the worker only stops once we drop the sender, so this cannot fail
+        let _ = self.requests.send(PathRequest {
+            generation: self.generation,
+            radar: radar.clone(),
+        });
+    }
+
+    /// Returns the result of the newest request if it arrived, stale results are dropped.
+    fn poll(&mut self) -> Option<PathResult> {
+        let mut latest = None;
+        while let Ok(result) = self.results.try_recv() {
+            if self.accepts(&result) {
+                self.computing = false;
+                latest = Some(result);
+            }
+        }
+        latest
+    }
+
+    const fn accepts(&self, result: &PathResult) -> bool {
+        result.generation == self.generation
+    }
+
+    const fn is_computing(&self) -> bool {
+        self.computing
+    }
+
+    /// how far the worker got with its current computation, from 0 to 1
+    fn progress(&self) -> f32 {
+        f32::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+}
+
+/// The parts of [`App`] that input changes, for tests to check after feeding it keys.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct AppSnapshot {
+    submenu: Option<Submenu>,
+    error: Option<String>,
+    show_path_index: Option<usize>,
+    possible_paths: usize,
+}
+
 #[derive(Debug)]
 pub struct App {
     exit: bool,
     radar: Radar,
+    worker: PathWorker,
     possible_paths: Vec<Vec<DecoratedCoordinate>>,
+    possibilities: PossibilitySet,
+    empty_reason: Option<EmptyReason>,
+    /// moves after which the possible positions stayed the same
+    unchanged_for: usize,
+    /// a silence that can only have gone one way, offered to be committed
+    settled_silence: Option<(usize, Direction, u8)>,
     show_path_index: Option<usize>,
     submenu: Option<Submenu>,
     error: Option<AppError>,
+    sessions: SessionStore,
+    /// possible position counts of the listed sessions, by index, as they are counted
+    session_counts: Option<Receiver<(usize, usize)>>,
+    /// typed numeric prefix for the next move
+    repeat: String,
+    show_help: bool,
+    charset: Charset,
+    /// actions applied since the macro recording started
+    recording: Option<Vec<Action>>,
+    /// how long to wait for input while paths are being computed
+    poll_interval: Duration,
+    /// shows this many of the most likely positions instead of a path
+    top: Option<usize>,
+    /// shows the probability of each cell instead of a path
+    numbers: bool,
+    /// keeps drawing the path from before the latest surface
+    ghost: bool,
+    /// obstacle toggles, with their own undo and redo
+    map_edits: MapEdits,
+    /// shows the cells reachable within this many turns instead of a path
+    cone_turns: Option<usize>,
+    /// cells reachable within `cone_turns`, kept up to date with the possible paths
+    cone: HashSet<Coordinate>,
+    /// draws only the part of the map around the possible positions
+    follow: bool,
+    /// shows a prefix of the trace instead of all of it
+    replay: Option<Replay>,
+    /// when the replay was last advanced
+    last_tick: Instant,
 }
 
 impl App {
+    const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+    /// how many times longer to wait for input when there is nothing to pick up from the worker
+    const IDLE_POLL_FACTOR: u32 = 10;
+    /// unchanged moves after which a drone is suggested
+    const STALE_MOVES: usize = 3;
+    /// how many of the most likely positions to show when no count was typed
+    const DEFAULT_TOP: usize = 5;
+    /// cells shown around the possible positions when following them
+    const VIEW_MARGIN: u32 = 2;
+    /// possible positions at or below which each one's probability is listed
+    const LOCATED_CELLS: usize = 5;
+
     pub fn new(radar: Radar) -> Self {
         let mut this = Self {
             exit: false,
             radar,
+            sessions: SessionStore::new(default_session_dir()),
+            session_counts: None,
+            repeat: String::new(),
+            show_help: false,
+            charset: Charset::Unicode,
+            recording: None,
+            poll_interval: Self::DEFAULT_POLL_INTERVAL,
+            top: None,
+            numbers: false,
+            ghost: true,
+            map_edits: MapEdits::default(),
+            cone_turns: None,
+            cone: HashSet::new(),
+            follow: false,
+            replay: None,
+            last_tick: Instant::now(),
+            worker: PathWorker::spawn(),
             possible_paths: vec![],
+            possibilities: PossibilitySet::default(),
+            empty_reason: None,
+            unchanged_for: 0,
+            settled_silence: None,
             show_path_index: None,
             submenu: None,
             error: None,
@@ -191,15 +1192,274 @@ impl App {
         this
     }
 
+    /// schedules a recomputation of the possible paths on the worker
     fn update_possible_paths(&mut self) {
-        self.possible_paths = self.radar.get_possible_paths().collect();
-        if self.possible_paths.is_empty() {
-            self.show_path_index = None;
-        } else {
-            self.show_path_index = Some(0);
+        match &self.replay {
+            Some(replay) => self.worker.request(&self.radar.trace_prefix(replay.step)),
+            None => self.worker.request(&self.radar),
+        }
+    }
+
+    fn receive_possible_paths(&mut self) {
+        let Some(result) = self.worker.poll() else {
+            return;
+        };
+
+        self.possible_paths = result.paths;
+        self.possibilities = result.possibilities;
+        self.empty_reason = result.empty_reason;
+        self.unchanged_for = result.unchanged_for;
+        self.settled_silence = result.settled_silence;
+        self.clamp_path_index();
+        self.update_cone();
+    }
+
+    fn update_cone(&mut self) {
+        self.cone = match self.cone_turns {
+            Some(turns) => self.possibilities.reachable_within(self.radar.map(), turns),
+            None => HashSet::new(),
+        };
+    }
+
+    /// keeps the shown path index pointing into `possible_paths`, or `None` if there are none
+    fn clamp_path_index(&mut self) {
+        self.show_path_index = match self.possible_paths.len() {
+            0 => None,
+            len => Some(self.show_path_index.unwrap_or(0).min(len - 1)),
+        };
+    }
+
+    /// turns water into an obstacle or back, eg. when the board was entered wrong
+    fn toggle_obstacle(&mut self, cell: Coordinate) -> Result<(), MapError> {
+        let mut map = self.radar.map().clone();
+        map.toggle_obstacle(cell)?;
+        self.radar.set_map(map);
+        self.update_possible_paths();
+        Ok(())
+    }
+
+    /// registers `count` moves in `direction`, stopping at the first illegal one
+    fn register_burst(&mut self, direction: Direction, count: usize) {
+        self.error = register_burst(&mut self.radar, direction, count).err();
+        self.update_possible_paths();
+    }
+
+    /// applies a decoded user action to the app state
+    fn apply_action(&mut self, action: Action) {
+        if let Some(recording) = &mut self.recording {
+            if !matches!(
+                action,
+                Action::NextPath
+                    | Action::ToggleHelp
+                    | Action::ToggleTop(_)
+                    | Action::ToggleNumbers
+                    | Action::ToggleGhost
+                    | Action::ToggleCone
+                    | Action::AdjustCone(_)
+                    | Action::ToggleCharset
+                    | Action::ToggleFollow
+                    | Action::Quit
+            ) {
+                recording.push(action.clone());
+            }
+        }
+
+        match action {
+            Action::Move(direction) => self.register_burst(direction, 1),
+            Action::Burst { direction, count } => self.register_burst(direction, count),
+            Action::Silence => {
+                self.error = self
+                    .radar
+                    .register_move(Move::Dash)
+                    .err()
+                    .map(|e| AppError::Sonar(e.into()));
+                self.update_possible_paths();
+            }
+            Action::Surface => {
+                self.error = self
+                    .radar
+                    .register_move(Move::Surface)
+                    .err()
+                    .map(|e| AppError::Sonar(e.into()));
+                self.update_possible_paths();
+            }
+            Action::Mine => {
+                self.radar.plant_mine();
+                self.update_possible_paths();
+            }
+            Action::Intel(intel) => self.add_intel(intel),
+            Action::Undo => {
+                self.radar.undo_trace();
+                self.update_possible_paths();
+            }
+            Action::UndoToLastIntel => {
+                self.radar.undo_to_last_intel();
+                self.update_possible_paths();
+            }
+            Action::UndoToLastSurface => {
+                self.radar.undo_to_last_surface();
+                self.update_possible_paths();
+            }
+            Action::ClearIntel => {
+                self.radar.clear_intel();
+                self.update_possible_paths();
+            }
+            Action::CommitSilence {
+                index,
+                direction,
+                distance,
+            } => {
+                self.error = self
+                    .radar
+                    .commit_silence(index, direction, distance)
+                    .err()
+                    .map(|e| AppError::Sonar(e.into()));
+                self.update_possible_paths();
+            }
+            Action::NextPath => {
+                if let Some(index) = self.show_path_index {
+                    self.show_path_index = Some((index + 1) % self.possible_paths.len().max(1));
+                    self.clamp_path_index();
+                }
+            }
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::ToggleTop(count) => {
+                self.top = match self.top {
+                    Some(_) => None,
+                    None => Some(count),
+                };
+            }
+            Action::ToggleCharset => self.charset = self.charset.toggled(),
+            Action::ToggleFollow => self.follow = !self.follow,
+            Action::ToggleNumbers => self.numbers = !self.numbers,
+            Action::ToggleGhost => self.ghost = !self.ghost,
+            Action::ToggleCone => {
+                self.cone_turns = match self.cone_turns {
+                    Some(_) => None,
+                    None => Some(1),
+                };
+                self.update_cone();
+            }
+            Action::AdjustCone(by) => {
+                if let Some(turns) = self.cone_turns {
+                    self.cone_turns = Some(
+                        turns
+                            .saturating_add_signed(by)
+                            .clamp(1, Radar::MAX_REACHABLE_TURNS),
+                    );
+                    self.update_cone();
+                }
+            }
+            Action::Quit => self.exit(),
+        }
+    }
+
+    /// adds the intel unless it contradicts earlier intel, and closes the submenu
+    fn add_intel(&mut self, intel: IntelQuestion) {
+        self.error = self
+            .radar
+            .try_add_intel(intel)
+            .err()
+            .map(|e| AppError::Sonar(e.into()));
+        self.submenu = None;
+        self.update_possible_paths();
+    }
+
+    /// keys while stepping through the trace, leaving it shows the whole trace again
+    fn handle_replay_key(&mut self, code: KeyCode) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+
+        let before = replay.step;
+        match code {
+            KeyCode::Left => replay.step_by(-1),
+            KeyCode::Right => replay.step_by(1),
+            KeyCode::Char(' ') => {
+                replay.toggle_playing();
+                self.last_tick = Instant::now();
+            }
+            KeyCode::Char('+') => replay.change_speed(true),
+            KeyCode::Char('-') => replay.change_speed(false),
+            KeyCode::Char('?') => self.apply_action(Action::ToggleHelp),
+            KeyCode::Char('p') | KeyCode::Backspace => {
+                self.replay = None;
+                self.update_possible_paths();
+                return;
+            }
+            _ => return,
+        }
+
+        if self
+            .replay
+            .as_ref()
+            .is_some_and(|replay| replay.step != before)
+        {
+            self.update_possible_paths();
+        }
+    }
+
+    /// applies the actions in order, stopping at the first one that fails
+    fn replay(&mut self, actions: Vec<Action>) {
+        self.error = None;
+        for action in actions {
+            self.apply_action(action);
+            if self.error.is_some() {
+                break;
+            }
         }
     }
 
+    /// Lists the saved sessions and counts their possible positions on a background thread,
+    /// as that needs every session loaded and analyzed.
+    fn list_sessions(&mut self) -> Result<Vec<(String, Option<usize>)>, SessionError> {
+        let names = self.sessions.list()?;
+        let (count_tx, count_rx) = mpsc::channel();
+        let store = self.sessions.clone();
+        let to_count = names.clone();
+        thread::spawn(move || {
+            for (i, name) in to_count.iter().enumerate() {
+                // a session that fails to load reports its error once picked
+                let Ok(radar) = store.load(name) else {
+                    continue;
+                };
+                if count_tx
+                    .send((i, radar.count_possible_positions()))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        self.session_counts = Some(count_rx);
+
+        Ok(names.into_iter().map(|name| (name, None)).collect())
+    }
+
+    fn receive_session_counts(&mut self) {
+        let Some(counts) = &self.session_counts else {
+            return;
+        };
+        let Some(Submenu::LoadSession { sessions, .. }) = &mut self.submenu else {
+            // the picker was closed, dropping the receiver stops the counting
+            self.session_counts = None;
+            return;
+        };
+        loop {
+            match counts.try_recv() {
+                Ok((i, count)) => {
+                    if let Some((_, slot)) = sessions.get_mut(i) {
+                        *slot = Some(count);
+                    }
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        // every session is counted
+        self.session_counts = None;
+    }
+
     /// runs the application's main loop until the user quits
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         while !self.exit {
@@ -213,28 +1473,71 @@ impl App {
         frame.render_widget(self, frame.area());
     }
 
+    /// how long to wait for input before checking on the worker again
+    fn poll_timeout(&self) -> Duration {
+        let timeout = if self.worker.is_computing() || self.session_counts.is_some() {
+            self.poll_interval
+        } else {
+            self.poll_interval * Self::IDLE_POLL_FACTOR
+        };
+
+        match self.replay.as_ref().and_then(Replay::until_next) {
+            Some(next) => timeout.min(next),
+            None => timeout,
+        }
+    }
+
+    /// advances a playing replay by the time passed since the last tick
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let passed = now - self.last_tick;
+        self.last_tick = now;
+
+        if self
+            .replay
+            .as_mut()
+            .is_some_and(|replay| replay.tick(passed))
+        {
+            self.update_possible_paths();
+        }
+    }
+
     /// updates the application's state based on user input
     fn handle_events(&mut self) -> io::Result<()> {
-        if !event::poll(std::time::Duration::from_millis(100))? {
+        self.tick();
+        self.receive_possible_paths();
+        self.receive_session_counts();
+        if !event::poll(self.poll_timeout())? {
             return Ok(());
         }
         match event::read()? {
             // it's important to check that the event is a key press event as
             // crossterm also emits key release and repeat events on Windows.
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event);
+                self.handle_key_code(key_event.code);
             }
+            // a resize only needs the redraw that follows every event,
+            // which picks the grid style and view for the new size
             _ => {}
         };
         Ok(())
     }
 
-    fn handle_key_event(&mut self, key_event: KeyEvent) {
-        fn base_handling(app: &mut App, key_event: KeyEvent) -> bool {
-            match key_event.code {
-                KeyCode::Esc => {
-                    app.exit();
-                }
+    #[cfg(test)]
+    fn snapshot(&self) -> AppSnapshot {
+        AppSnapshot {
+            submenu: self.submenu.clone(),
+            error: self.error.as_ref().map(ToString::to_string),
+            show_path_index: self.show_path_index,
+            possible_paths: self.possible_paths.len(),
+        }
+    }
+
+    /// handles a key press, independent of how the key event was obtained
+    fn handle_key_code(&mut self, code: KeyCode) {
+        fn base_handling(app: &mut App, code: KeyCode) -> bool {
+            match code {
+                KeyCode::Esc => app.apply_action(Action::Quit),
                 KeyCode::Backspace => {
                     if app.error.is_some() {
                         app.error = None;
@@ -250,8 +1553,8 @@ impl App {
             true
         }
 
-        const fn read_quadrant(key_event: KeyEvent) -> Option<Quadrant> {
-            match key_event.code {
+        const fn read_quadrant(code: KeyCode) -> Option<Quadrant> {
+            match code {
                 KeyCode::Char('1') => Some(Quadrant::One),
                 KeyCode::Char('2') => Some(Quadrant::Two),
                 KeyCode::Char('3') => Some(Quadrant::Three),
@@ -260,8 +1563,8 @@ impl App {
             }
         }
 
-        const fn read_truth_lie_kind(key_event: KeyEvent) -> Option<PickTruthLieKind> {
-            match key_event.code {
+        const fn read_truth_lie_kind(code: KeyCode) -> Option<PickTruthLieKind> {
+            match code {
                 KeyCode::Char('q') => Some(PickTruthLieKind::Quadrant),
                 KeyCode::Char('r') => Some(PickTruthLieKind::Row),
                 KeyCode::Char('c') => Some(PickTruthLieKind::Column),
@@ -271,15 +1574,13 @@ impl App {
 
         fn read_information_piece(
             app: &App,
-            key_event: KeyEvent,
+            code: KeyCode,
             kind: PickTruthLieKind,
         ) -> Option<InformationPiece> {
             match kind {
-                PickTruthLieKind::Quadrant => {
-                    read_quadrant(key_event).map(InformationPiece::Quadrant)
-                }
+                PickTruthLieKind::Quadrant => read_quadrant(code).map(InformationPiece::Quadrant),
                 PickTruthLieKind::Row => panic!("Row should not be handled here"),
-                PickTruthLieKind::Column => match key_event.code {
+                PickTruthLieKind::Column => match code {
                     KeyCode::Char(c) => {
                         // make sure we don't undeflow
                         if c >= 'a' {
@@ -296,102 +1597,456 @@ impl App {
             }
         }
 
-        const fn read_digit(key_event: KeyEvent) -> Option<char> {
-            match key_event.code {
-                KeyCode::Char(c @ '0'..='9') => Some(c),
-                _ => None,
+        const fn read_digit(code: KeyCode) -> Option<char> {
+            match code {
+                KeyCode::Char(c @ '0'..='9') => Some(c),
+                _ => None,
+            }
+        }
+
+        if base_handling(self, code) {
+            return;
+        }
+
+        if self.replay.is_some() && self.submenu.is_none() {
+            self.handle_replay_key(code);
+            return;
+        }
+
+        // numeric prefix for repeated moves, any other key consumes it
+        let repeat = std::mem::take(&mut self.repeat);
+
+        match &self.submenu {
+            None => {
+                let directed = |direction| match repeat.parse() {
+                    Ok(count) => Action::Burst { direction, count },
+                    Err(_) => Action::Move(direction),
+                };
+                let action = match code {
+                    KeyCode::Char(c @ '0'..='9') => {
+                        self.repeat = repeat + &c.to_string();
+                        return;
+                    }
+                    KeyCode::Backspace => Action::Undo,
+                    KeyCode::Up => directed(Direction::North),
+                    KeyCode::Down => directed(Direction::South),
+                    KeyCode::Left => directed(Direction::West),
+                    KeyCode::Right => directed(Direction::East),
+                    KeyCode::Char('d') => Action::Silence,
+                    KeyCode::Char('f') => Action::Surface,
+                    KeyCode::Char('m') => Action::Mine,
+                    KeyCode::Char('?') => Action::ToggleHelp,
+                    KeyCode::Char('a') => Action::ToggleCharset,
+                    KeyCode::Char('z') => Action::ToggleFollow,
+                    KeyCode::Char('n') => Action::ToggleNumbers,
+                    KeyCode::Char('g') => Action::ToggleGhost,
+                    KeyCode::Char('c') => Action::ToggleCone,
+                    KeyCode::Char('+') => Action::AdjustCone(1),
+                    KeyCode::Char('-') => Action::AdjustCone(-1),
+                    KeyCode::Char('k') => {
+                        Action::ToggleTop(repeat.parse().unwrap_or(Self::DEFAULT_TOP))
+                    }
+                    KeyCode::Tab => Action::NextPath,
+                    KeyCode::Char('q') => {
+                        self.submenu = Some(Submenu::IntelPickQuadrant { quadrant: None });
+                        return;
+                    }
+                    KeyCode::Char('x') => {
+                        self.submenu = Some(Submenu::ExcludeQuadrants { quadrants: vec![] });
+                        return;
+                    }
+                    KeyCode::Char('s') => {
+                        self.submenu = Some(Submenu::IntelPickTruthLie(PickTruthLieProgress::None));
+                        return;
+                    }
+                    KeyCode::Char('t') => {
+                        self.submenu = Some(Submenu::Torpedo(TorpedoProgress::Target {
+                            input: String::new(),
+                            hint: None,
+                        }));
+                        return;
+                    }
+                    KeyCode::Char('l') => {
+                        self.submenu = Some(Submenu::LineHit(LineHitProgress::From {
+                            input: String::new(),
+                            hint: None,
+                        }));
+                        return;
+                    }
+                    KeyCode::Char('e') => {
+                        self.submenu = Some(Submenu::CellIntel(CellIntelProgress::Cell {
+                            input: String::new(),
+                            hint: None,
+                        }));
+                        return;
+                    }
+                    KeyCode::Char('o') => {
+                        self.submenu = Some(Submenu::EditObstacle {
+                            input: String::new(),
+                            hint: None,
+                        });
+                        return;
+                    }
+                    KeyCode::Char(key @ ('[' | ']')) => {
+                        let cell = match key {
+                            '[' => self.map_edits.undo(),
+                            _ => self.map_edits.redo(),
+                        };
+                        if let Some(cell) = cell {
+                            self.error = self
+                                .toggle_obstacle(cell)
+                                .err()
+                                .map(|e| AppError::Sonar(e.into()));
+                        }
+                        return;
+                    }
+                    KeyCode::Char('S') => {
+                        self.submenu = Some(Submenu::SaveSession {
+                            name: String::new(),
+                        });
+                        return;
+                    }
+                    KeyCode::Char('U') => Action::UndoToLastIntel,
+                    KeyCode::Char('F') => Action::UndoToLastSurface,
+                    KeyCode::Char('y') => match self.settled_silence {
+                        Some((index, direction, distance)) => Action::CommitSilence {
+                            index,
+                            direction,
+                            distance,
+                        },
+                        None => return,
+                    },
+                    KeyCode::Char('p') => {
+                        self.replay = Some(Replay::new(self.radar.trace().len()));
+                        self.last_tick = Instant::now();
+                        self.update_possible_paths();
+                        return;
+                    }
+                    KeyCode::Char('C') => {
+                        self.submenu = Some(Submenu::ConfirmClearIntel);
+                        return;
+                    }
+                    KeyCode::Char('h') => {
+                        self.submenu = Some(Submenu::PickHalf);
+                        return;
+                    }
+                    KeyCode::Char('r') => {
+                        match self.recording.take() {
+                            None => self.recording = Some(vec![]),
+                            Some(actions) => {
+                                self.submenu = Some(Submenu::SaveMacro {
+                                    name: String::new(),
+                                    actions,
+                                });
+                            }
+                        }
+                        return;
+                    }
+                    KeyCode::Char('M') => {
+                        match self.sessions.list_macros() {
+                            Ok(macros) => {
+                                self.submenu = Some(Submenu::PickMacro {
+                                    macros,
+                                    selected: 0,
+                                });
+                            }
+                            Err(e) => self.error = Some(AppError::Sonar(e.into())),
+                        }
+                        return;
+                    }
+                    KeyCode::Char('L') => {
+                        match self.list_sessions() {
+                            Ok(sessions) => {
+                                self.submenu = Some(Submenu::LoadSession {
+                                    sessions,
+                                    selected: 0,
+                                });
+                            }
+                            Err(e) => self.error = Some(AppError::Sonar(e.into())),
+                        }
+                        return;
+                    }
+                    _ => return,
+                };
+                self.apply_action(action);
+            }
+            Some(Submenu::IntelPickQuadrant { quadrant: None }) => {
+                self.submenu = Some(Submenu::IntelPickQuadrant {
+                    quadrant: read_quadrant(code),
+                });
+            }
+            &Some(Submenu::IntelPickQuadrant {
+                quadrant: Some(quadrant),
+            }) => {
+                let answer = match code {
+                    KeyCode::Char('y') => true,
+                    KeyCode::Char('n') => false,
+                    _ => return,
+                };
+
+                self.apply_action(Action::Intel(IntelQuestion::InQuadrant {
+                    quadrant,
+                    answer,
+                }));
+            }
+            Some(Submenu::ExcludeQuadrants { quadrants }) => {
+                if code == KeyCode::Enter {
+                    if !quadrants.is_empty() {
+                        self.apply_action(Action::Intel(IntelQuestion::NotInQuadrants {
+                            quadrants: quadrants.clone(),
+                        }));
+                    }
+                } else if let Some(quadrant) = read_quadrant(code) {
+                    let mut quadrants = quadrants.clone();
+                    if let Some(i) = quadrants.iter().position(|&q| q == quadrant) {
+                        quadrants.remove(i);
+                    } else {
+                        quadrants.push(quadrant);
+                    }
+                    self.submenu = Some(Submenu::ExcludeQuadrants { quadrants });
+                }
+            }
+            Some(Submenu::Torpedo(TorpedoProgress::Target { input, .. })) => match code {
+                KeyCode::Enter => {
+                    let progress = match input.parse::<Coordinate>() {
+                        Err(e) => TorpedoProgress::Target {
+                            input: input.clone(),
+                            hint: Some(e.to_string()),
+                        },
+                        Ok(target) if !self.radar.map().is_open(target) => {
+                            TorpedoProgress::Target {
+                                input: input.clone(),
+                                hint: Some(format!("{target} is not on open water")),
+                            }
+                        }
+                        Ok(target) => TorpedoProgress::Aimed {
+                            target,
+                            coverage: self.possibilities.torpedo_coverage(target),
+                            probability: self.possibilities.torpedo_hit_probability(target),
+                            best: self.possibilities.best_torpedo_target(self.radar.map()),
+                        },
+                    };
+                    self.submenu = Some(Submenu::Torpedo(progress));
+                }
+                KeyCode::Char(c) if c.is_ascii_alphanumeric() => {
+                    self.submenu = Some(Submenu::Torpedo(TorpedoProgress::Target {
+                        input: input.to_owned() + &c.to_string(),
+                        hint: None,
+                    }));
+                }
+                _ => (),
+            },
+            Some(Submenu::LineHit(LineHitProgress::From { input, .. })) => match code {
+                KeyCode::Enter => {
+                    let progress = match input.parse::<Coordinate>() {
+                        Err(e) => LineHitProgress::From {
+                            input: input.clone(),
+                            hint: Some(e.to_string()),
+                        },
+                        Ok(from) if !self.radar.map().contains(from) => LineHitProgress::From {
+                            input: input.clone(),
+                            hint: Some(format!("{from} is not on the map")),
+                        },
+                        Ok(from) => LineHitProgress::Direction { from },
+                    };
+                    self.submenu = Some(Submenu::LineHit(progress));
+                }
+                KeyCode::Char(c) if c.is_ascii_alphanumeric() => {
+                    self.submenu = Some(Submenu::LineHit(LineHitProgress::From {
+                        input: input.to_owned() + &c.to_string(),
+                        hint: None,
+                    }));
+                }
+                _ => (),
+            },
+            &Some(Submenu::LineHit(LineHitProgress::Direction { from })) => {
+                let direction = match code {
+                    KeyCode::Up => Direction::North,
+                    KeyCode::Right => Direction::East,
+                    KeyCode::Down => Direction::South,
+                    KeyCode::Left => Direction::West,
+                    _ => return,
+                };
+
+                self.apply_action(Action::Intel(IntelQuestion::LineHit {
+                    from,
+                    direction,
+                    range: TORPEDO_RANGE,
+                }));
             }
-        }
-
-        if base_handling(self, key_event) {
-            return;
-        }
+            Some(Submenu::CellIntel(CellIntelProgress::Cell { input, .. })) => match code {
+                KeyCode::Enter => {
+                    let progress = match input.parse::<Coordinate>() {
+                        Err(e) => CellIntelProgress::Cell {
+                            input: input.clone(),
+                            hint: Some(e.to_string()),
+                        },
+                        Ok(coord) if !self.radar.map().is_open(coord) => CellIntelProgress::Cell {
+                            input: input.clone(),
+                            hint: Some(format!("{coord} is not on open water")),
+                        },
+                        Ok(coord) => CellIntelProgress::Answer { coord },
+                    };
+                    self.submenu = Some(Submenu::CellIntel(progress));
+                }
+                KeyCode::Char(c) if c.is_ascii_alphanumeric() => {
+                    self.submenu = Some(Submenu::CellIntel(CellIntelProgress::Cell {
+                        input: input.to_owned() + &c.to_string(),
+                        hint: None,
+                    }));
+                }
+                _ => (),
+            },
+            &Some(Submenu::CellIntel(CellIntelProgress::Answer { coord })) => {
+                let present = match code {
+                    KeyCode::Char('y') => true,
+                    KeyCode::Char('n') => false,
+                    _ => return,
+                };
 
-        match &self.submenu {
-            None => match key_event.code {
-                KeyCode::Backspace => {
-                    self.radar.undo_trace();
-                    self.update_possible_paths();
+                self.apply_action(Action::Intel(IntelQuestion::Cell { coord, present }));
+            }
+            Some(Submenu::EditObstacle { input, .. }) => match code {
+                KeyCode::Enter => match input.parse::<Coordinate>() {
+                    Err(e) => {
+                        self.submenu = Some(Submenu::EditObstacle {
+                            input: input.clone(),
+                            hint: Some(e.to_string()),
+                        });
+                    }
+                    Ok(cell) => match self.toggle_obstacle(cell) {
+                        Err(e) => {
+                            self.submenu = Some(Submenu::EditObstacle {
+                                input: cell.to_string(),
+                                hint: Some(e.to_string()),
+                            });
+                        }
+                        Ok(()) => {
+                            self.map_edits.record(cell);
+                            self.submenu = None;
+                        }
+                    },
+                },
+                KeyCode::Char(c) if c.is_ascii_alphanumeric() => {
+                    self.submenu = Some(Submenu::EditObstacle {
+                        input: input.to_owned() + &c.to_string(),
+                        hint: None,
+                    });
                 }
-                KeyCode::Up => {
-                    self.error = self
-                        .radar
-                        .register_move(Move::Directed(Direction::North))
-                        .err()
-                        .map(AppError::Move);
-                    self.update_possible_paths();
+                _ => (),
+            },
+            Some(Submenu::Torpedo(TorpedoProgress::Aimed { .. })) => {
+                if code == KeyCode::Enter {
+                    self.submenu = None;
                 }
-                KeyCode::Down => {
+            }
+            Some(Submenu::SaveSession { name }) => match code {
+                KeyCode::Enter => {
                     self.error = self
-                        .radar
-                        .register_move(Move::Directed(Direction::South))
+                        .sessions
+                        .save(name, &self.radar)
                         .err()
-                        .map(AppError::Move);
-                    self.update_possible_paths();
+                        .map(|e| AppError::Sonar(e.into()));
+                    self.submenu = None;
                 }
-                KeyCode::Left => {
-                    self.error = self
-                        .radar
-                        .register_move(Move::Directed(Direction::West))
-                        .err()
-                        .map(AppError::Move);
-                    self.update_possible_paths();
+                KeyCode::Char(c) if is_name_char(c) => {
+                    self.submenu = Some(Submenu::SaveSession {
+                        name: name.to_owned() + &c.to_string(),
+                    });
                 }
-                KeyCode::Right => {
-                    self.error = self
-                        .radar
-                        .register_move(Move::Directed(Direction::East))
-                        .err()
-                        .map(AppError::Move);
-                    self.update_possible_paths();
+                _ => (),
+            },
+            Some(Submenu::PickHalf) => {
+                let (axis, side) = match code {
+                    KeyCode::Up => (Axis::Horizontal, Side::Start),
+                    KeyCode::Down => (Axis::Horizontal, Side::End),
+                    KeyCode::Left => (Axis::Vertical, Side::Start),
+                    KeyCode::Right => (Axis::Vertical, Side::End),
+                    _ => return,
+                };
+
+                self.apply_action(Action::Intel(IntelQuestion::Half { axis, side }));
+            }
+            Some(Submenu::ConfirmClearIntel) => match code {
+                KeyCode::Char('y') => {
+                    self.submenu = None;
+                    self.apply_action(Action::ClearIntel);
                 }
-                KeyCode::Char('d') => {
+                KeyCode::Char('n') => self.submenu = None,
+                _ => (),
+            },
+            Some(Submenu::SaveMacro { name, actions }) => match code {
+                KeyCode::Enter => {
                     self.error = self
-                        .radar
-                        .register_move(Move::Dash)
+                        .sessions
+                        .save_macro(name, actions)
                         .err()
-                        .map(AppError::Move);
-                    self.update_possible_paths();
+                        .map(|e| AppError::Sonar(e.into()));
+                    self.submenu = None;
                 }
-                KeyCode::Char('m') => {
-                    self.radar.plant_mine();
-                    self.update_possible_paths();
+                KeyCode::Char(c) if is_name_char(c) => {
+                    self.submenu = Some(Submenu::SaveMacro {
+                        name: name.to_owned() + &c.to_string(),
+                        actions: actions.clone(),
+                    });
                 }
-                KeyCode::Char('q') => {
-                    self.submenu = Some(Submenu::IntelPickQuadrant { quadrant: None });
+                _ => (),
+            },
+            Some(Submenu::PickMacro { macros, selected }) => match code {
+                KeyCode::Up => {
+                    self.submenu = Some(Submenu::PickMacro {
+                        macros: macros.clone(),
+                        selected: selected.saturating_sub(1),
+                    });
                 }
-                KeyCode::Char('s') => {
-                    self.submenu = Some(Submenu::IntelPickTruthLie(PickTruthLieProgress::None));
+                KeyCode::Down => {
+                    self.submenu = Some(Submenu::PickMacro {
+                        macros: macros.clone(),
+                        selected: (selected + 1).min(macros.len().saturating_sub(1)),
+                    });
                 }
-                KeyCode::Tab => {
-                    if let Some(index) = self.show_path_index {
-                        self.show_path_index = Some((index + 1) % self.possible_paths.len());
+                KeyCode::Enter => {
+                    if let Some(name) = macros.get(*selected) {
+                        let actions = self.sessions.load_macro(name);
+                        self.submenu = None;
+                        match actions {
+                            Ok(actions) => self.replay(actions),
+                            Err(e) => self.error = Some(AppError::Sonar(e.into())),
+                        }
+                    }
+                }
+                _ => (),
+            },
+            Some(Submenu::LoadSession { sessions, selected }) => match code {
+                KeyCode::Up => {
+                    self.submenu = Some(Submenu::LoadSession {
+                        sessions: sessions.clone(),
+                        selected: selected.saturating_sub(1),
+                    });
+                }
+                KeyCode::Down => {
+                    self.submenu = Some(Submenu::LoadSession {
+                        sessions: sessions.clone(),
+                        selected: (selected + 1).min(sessions.len().saturating_sub(1)),
+                    });
+                }
+                KeyCode::Enter => {
+                    if let Some((name, _)) = sessions.get(*selected) {
+                        match self.sessions.load(name) {
+                            Ok(radar) => {
+                                self.radar = radar;
+                                self.update_possible_paths();
+                            }
+                            Err(e) => self.error = Some(AppError::Sonar(e.into())),
+                        }
+                        self.submenu = None;
                     }
                 }
                 _ => (),
             },
-            Some(Submenu::IntelPickQuadrant { quadrant: None }) => {
-                self.submenu = Some(Submenu::IntelPickQuadrant {
-                    quadrant: read_quadrant(key_event),
-                });
-            }
-            &Some(Submenu::IntelPickQuadrant {
-                quadrant: Some(quadrant),
-            }) => {
-                let answer = match key_event.code {
-                    KeyCode::Char('y') => true,
-                    KeyCode::Char('n') => false,
-                    _ => return,
-                };
-
-                self.radar
-                    .add_intel(IntelQuestion::InQuadrant { quadrant, answer });
-                self.submenu = None;
-                self.update_possible_paths();
-            }
             Some(Submenu::IntelPickTruthLie(progress)) => match progress {
                 PickTruthLieProgress::None => {
-                    if let Some(kind) = read_truth_lie_kind(key_event) {
+                    if let Some(kind) = read_truth_lie_kind(code) {
                         self.submenu = Some(Submenu::IntelPickTruthLie(
                             PickTruthLieProgress::Info1Kind {
                                 kind,
@@ -404,16 +2059,16 @@ impl App {
                     kind: PickTruthLieKind::Row,
                     data,
                 } => {
-                    if key_event.code == KeyCode::Enter {
+                    if code == KeyCode::Enter {
                         if let Ok(row) = data.parse::<u32>() {
-                            if row <= self.radar.map().size() {
+                            if (1..=self.radar.map().size()).contains(&row) {
                                 let info1 = InformationPiece::Row(row - 1);
                                 self.submenu = Some(Submenu::IntelPickTruthLie(
                                     PickTruthLieProgress::Info1Information(info1),
                                 ));
                             }
                         }
-                    } else if let Some(d) = read_digit(key_event) {
+                    } else if let Some(d) = read_digit(code) {
                         let data = data.to_owned() + &d.to_string();
                         self.submenu = Some(Submenu::IntelPickTruthLie(
                             PickTruthLieProgress::Info1Kind {
@@ -424,14 +2079,14 @@ impl App {
                     }
                 }
                 &PickTruthLieProgress::Info1Kind { kind, data: _ } => {
-                    if let Some(info) = read_information_piece(self, key_event, kind) {
+                    if let Some(info) = read_information_piece(self, code, kind) {
                         self.submenu = Some(Submenu::IntelPickTruthLie(
                             PickTruthLieProgress::Info1Information(info),
                         ));
                     }
                 }
                 &PickTruthLieProgress::Info1Information(info1) => {
-                    if let Some(kind) = read_truth_lie_kind(key_event) {
+                    if let Some(kind) = read_truth_lie_kind(code) {
                         self.submenu = Some(Submenu::IntelPickTruthLie(
                             PickTruthLieProgress::Info2Kind {
                                 info1,
@@ -446,19 +2101,17 @@ impl App {
                     kind: kind @ PickTruthLieKind::Row,
                     data,
                 } => {
-                    if key_event.code == KeyCode::Enter {
+                    if code == KeyCode::Enter {
                         if let Ok(row) = data.parse::<u32>() {
-                            if row <= self.radar.map().size() {
+                            if (1..=self.radar.map().size()).contains(&row) {
                                 let info2 = InformationPiece::Row(row - 1);
-                                self.radar.add_intel(IntelQuestion::TruthLie {
+                                self.apply_action(Action::Intel(IntelQuestion::TruthLie {
                                     info1: *info1,
                                     info2,
-                                });
-                                self.submenu = None;
-                                self.update_possible_paths();
+                                }));
                             }
                         }
-                    } else if let Some(d) = read_digit(key_event) {
+                    } else if let Some(d) = read_digit(code) {
                         let data = data.to_owned() + &d.to_string();
                         self.submenu = Some(Submenu::IntelPickTruthLie(
                             PickTruthLieProgress::Info2Kind {
@@ -474,11 +2127,8 @@ impl App {
                     kind,
                     data: _,
                 } => {
-                    if let Some(info2) = read_information_piece(self, key_event, kind) {
-                        self.radar
-                            .add_intel(IntelQuestion::TruthLie { info1, info2 });
-                        self.submenu = None;
-                        self.update_possible_paths();
+                    if let Some(info2) = read_information_piece(self, code, kind) {
+                        self.apply_action(Action::Intel(IntelQuestion::TruthLie { info1, info2 }));
                     }
                 }
             },
@@ -494,30 +2144,53 @@ impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         const BASE_INSTRUCTIONS: &str = "
 backspace - undo
+U - undo everything after the latest intel
+F - undo the latest surface and everything after it
 ESC - quit";
-
-        let instructions = format!(
-            "
-
-↑ - north, → - east, ↓ - south, ← - west
+        const KEYMAP: &str = "↑ - north, → - east, ↓ - south, ← - west
+0-9 - repeat the next move
 tab - next path
 d - dash
+f - surface, the enemy may cross its old trail again
+y - commit a silence whose distance became clear
 m - plant mine
 q - collect quadrant intel (drone)
+x - exclude several quadrants (drones)
+h - collect intel about a half of the board
 s - collect truth/lie intel (sonar)
-{}",
-            BASE_INSTRUCTIONS
-        );
+t - aim a torpedo
+l - torpedo line hit intel
+e - mark a cell where the enemy is or is not
+o - toggle an obstacle, [ / ] to undo / redo obstacle edits
+C - clear all intel
+r - start/stop recording a macro
+M - replay a macro
+p - step through the trace (←/→ step, space play/pause, +/- speed, p leave)
+S - save session
+L - load session
+k - show only the k most likely positions (5 unless a count is typed)
+n - toggle probabilities in percent
+g - toggle the path from before the latest surface (~)
+c - toggle cells reachable in the next turns, +/- to change the turns
+a - toggle ASCII grid
+z - toggle zooming in on the possible positions
+? - toggle this help
+backspace - undo
+ESC - quit";
 
+        const HELP_HINT: &str = "\n? - help";
         if let Some(error) = &self.error {
-            let text = Text::from(error.to_string() + &instructions);
+            let text = Text::from(error.to_string() + HELP_HINT);
             text.render(area, buf);
         } else if let Some(submenu) = &self.submenu {
+            let label = self.radar.map().quadrant_label();
             match submenu {
                 Submenu::IntelPickQuadrant { quadrant: None } => {
-                    let text = Text::from(
-                        "Pick a quadrant (1, 2, 3, 4)".to_string() + "\n" + BASE_INSTRUCTIONS,
-                    );
+                    let text = Text::from(format!(
+                        "Pick a quadrant ({})\n{}",
+                        quadrant_keys(label),
+                        BASE_INSTRUCTIONS
+                    ));
                     text.render(area, buf);
                 }
                 Submenu::IntelPickQuadrant {
@@ -525,14 +2198,170 @@ s - collect truth/lie intel (sonar)
                 } => {
                     let text = Text::from(format!(
                         "In quadrant {}? Pick answer (y / n)\n{}",
-                        quadrant, BASE_INSTRUCTIONS
+                        label.label(*quadrant),
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::ExcludeQuadrants { quadrants } => {
+                    let picked = quadrants
+                        .iter()
+                        .map(|&quadrant| label.label(quadrant))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let text = Text::from(format!(
+                        "Toggle excluded quadrants ({}) + ENTER: {picked}\n{}",
+                        quadrant_keys(label),
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::Torpedo(TorpedoProgress::Target { input, hint }) => {
+                    let text = Text::from(format!(
+                        "Torpedo target (eg. a1) + ENTER: {input}\n{}{}",
+                        hint.as_ref().map(|h| h.clone() + "\n").unwrap_or_default(),
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::Torpedo(TorpedoProgress::Aimed {
+                    target,
+                    coverage,
+                    probability,
+                    best,
+                }) => {
+                    let coverage = match coverage {
+                        TorpedoCoverage::All => "all",
+                        TorpedoCoverage::Some => "some",
+                        TorpedoCoverage::None => "none",
+                    };
+                    let best = match best {
+                        Some((best, p)) => format!("{best} ({:.0}%)", p * 100.0),
+                        None => "none".to_string(),
+                    };
+                    let text = Text::from(format!(
+                        "Torpedo at {target} damages {coverage} of the possible positions ({:.0}% hit chance)\nBest target: {best}\nENTER - done\n{}",
+                        probability * 100.0,
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::LineHit(LineHitProgress::From { input, hint }) => {
+                    let text = Text::from(format!(
+                        "Torpedo fired from (eg. a1) + ENTER: {input}\n{}{}",
+                        hint.as_ref().map(|h| h.clone() + "\n").unwrap_or_default(),
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::LineHit(LineHitProgress::Direction { from }) => {
+                    let text = Text::from(format!(
+                        "Torpedo fired from {from} hit in direction (↑, →, ↓, ←)\n{}",
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::CellIntel(CellIntelProgress::Cell { input, hint }) => {
+                    let text = Text::from(format!(
+                        "Cell (eg. e7) + ENTER: {input}\n{}{}",
+                        hint.as_ref().map(|h| h.clone() + "\n").unwrap_or_default(),
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::EditObstacle { input, hint } => {
+                    let text = Text::from(format!(
+                        "Cell to toggle an obstacle at (eg. e7) + ENTER: {input}\n{}{}",
+                        hint.as_ref().map(|h| h.clone() + "\n").unwrap_or_default(),
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::CellIntel(CellIntelProgress::Answer { coord }) => {
+                    let text = Text::from(format!(
+                        "Is the enemy at {coord}? Pick answer (y / n)\n{}",
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::SaveSession { name } => {
+                    let text = Text::from(format!(
+                        "Session name + ENTER: {name}\n{}",
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::PickHalf => {
+                    let text = Text::from(format!(
+                        "Half of the board the enemy is in (↑ north, ↓ south, ← west, → east)\n{}",
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::ConfirmClearIntel => {
+                    let text = Text::from(format!(
+                        "Clear all intel and keep only the moves? (y/n)\n{}",
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::SaveMacro { name, actions } => {
+                    let text = Text::from(format!(
+                        "Recorded {} actions, macro name + ENTER: {name}\n{}",
+                        actions.len(),
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::PickMacro { macros, selected } => {
+                    let list = if macros.is_empty() {
+                        "No saved macros".to_string()
+                    } else {
+                        macros
+                            .iter()
+                            .enumerate()
+                            .map(|(i, name)| {
+                                let marker = if i == *selected { ">" } else { " " };
+                                format!("{marker} {name}")
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    };
+                    let text = Text::from(format!(
+                        "Pick a macro to replay (↑/↓ + ENTER)\n{list}\n{}",
+                        BASE_INSTRUCTIONS
+                    ));
+                    text.render(area, buf);
+                }
+                Submenu::LoadSession { sessions, selected } => {
+                    let list = if sessions.is_empty() {
+                        "No saved sessions".to_string()
+                    } else {
+                        sessions
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (name, count))| {
+                                let marker = if i == *selected { ">" } else { " " };
+                                match count {
+                                    Some(count) => {
+                                        format!("{marker} {name} ({count} possible positions)")
+                                    }
+                                    None => format!("{marker} {name} (counting…)"),
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    };
+                    let text = Text::from(format!(
+                        "Pick a session (↑/↓ + ENTER)\n{list}\n{}",
+                        BASE_INSTRUCTIONS
                     ));
                     text.render(area, buf);
                 }
                 Submenu::IntelPickTruthLie(progress) => {
                     let kind_instruction = "q - quadrant, r - row, c - column";
                     let info_instruction = |kind| match kind {
-                        PickTruthLieKind::Quadrant => "1, 2, 3, 4".to_string(),
+                        PickTruthLieKind::Quadrant => quadrant_keys(label),
                         PickTruthLieKind::Row => (1..=self.radar.map().size())
                             .map(|n| n.to_string())
                             .collect::<Vec<_>>()
@@ -584,23 +2413,566 @@ s - collect truth/lie intel (sonar)
                     text.render(area, buf);
                 }
             }
-        } else if let Some(index) = self.show_path_index {
-            let path = &self.possible_paths[index];
+        } else if self.worker.is_computing() {
+            let [gauge_area, text_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+            Gauge::default()
+                .ratio(f64::from(self.worker.progress()).clamp(0.0, 1.0))
+                .label("Computing possible paths…")
+                .render(gauge_area, buf);
+            Text::from(HELP_HINT.trim_start()).render(text_area, buf);
+        } else if let Some((index, path)) = self
+            .show_path_index
+            .and_then(|index| Some((index, self.possible_paths.get(index)?)))
+        {
+            // once only a few cells are left, their probabilities are listed next to the grid
+            let (area, located) = if self.possibilities.count() <= App::LOCATED_CELLS {
+                let lines = confidence_lines(&self.possibilities.top_positions(App::LOCATED_CELLS));
+                let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u16 + 2;
+                let [area, side] =
+                    Layout::horizontal([Constraint::Fill(1), Constraint::Length(width)])
+                        .areas(area);
+                (area, Some((side, lines)))
+            } else {
+                (area, None)
+            };
+            if let Some((side, lines)) = located {
+                Text::from(lines.join("\n")).render(side, buf);
+            }
 
-            let mut s = radar_to_string(&self.radar, path);
+            let map = self.radar.map();
+            let view = if self.follow {
+                Viewport::around(
+                    self.possibilities.heatmap().keys().copied(),
+                    map.size(),
+                    App::VIEW_MARGIN,
+                    GridStyle::max_cells(area),
+                )
+            } else {
+                Viewport::full(map.size())
+            };
+            let style = GridStyle::for_area(area, view.width.max(view.height));
+            let mut s = match self.top {
+                Some(k) => {
+                    let top = self.possibilities.top_positions(k);
+                    let cells = top.into_iter().map(|(cell, _)| cell).collect();
+                    let glyphs = highlight_glyphs(map, &cells, self.charset);
+                    radar_to_string(map, view, style, self.charset, false, glyphs)
+                }
+                None if self.cone_turns.is_some() => {
+                    let glyphs = highlight_glyphs(map, &self.cone, self.charset);
+                    radar_to_string(map, view, style, self.charset, false, glyphs)
+                }
+                None if self.numbers => {
+                    let glyphs = number_glyphs(map, self.possibilities.heatmap());
+                    radar_to_string(map, view, style, self.charset, true, glyphs)
+                }
+                None => radar_to_string(
+                    map,
+                    view,
+                    style,
+                    self.charset,
+                    false,
+                    path_glyphs(map, path, self.ghost),
+                ),
+            };
             s.push('\n');
 
             s.push_str(&format!(
-                "Possible path: {}/{}",
+                "Path {}/{} from {} · {} positions · {:.0}% cornered · drift {}",
                 index + 1,
-                self.possible_paths.len()
+                self.possible_paths.len(),
+                path[0].coord(),
+                self.possibilities.count(),
+                self.possibilities.confinement_score(self.radar.map()) * 100.0,
+                compass(self.radar.net_displacement())
             ));
+            if !self.repeat.is_empty() {
+                s.push_str(&format!(" · repeat {}", self.repeat));
+            }
+            if let Some(k) = self.top {
+                s.push_str(&format!(" · top {k}"));
+            } else if let Some(turns) = self.cone_turns {
+                s.push_str(&format!(" · reachable within {turns} turns"));
+            }
+            if self.follow {
+                s.push_str(" · zoomed");
+            }
+            if let Some(recording) = &self.recording {
+                s.push_str(&format!(" · recording {}", recording.len()));
+            }
+            if let Some(replay) = &self.replay {
+                s.push_str(&format!(
+                    " · replay {}/{} {} every {} ms",
+                    replay.step,
+                    replay.len,
+                    if replay.playing { "playing" } else { "paused" },
+                    replay.interval.as_millis()
+                ));
+            }
+
+            // illegal directions are blanked out so the arrows keep their place
+            let legal = self.possibilities.legal_moves(self.radar.map());
+            let arrows = [
+                (Direction::North, '↑'),
+                (Direction::East, '→'),
+                (Direction::South, '↓'),
+                (Direction::West, '←'),
+            ]
+            .map(|(direction, arrow)| {
+                if legal.contains(&direction) {
+                    arrow
+                } else {
+                    '·'
+                }
+            });
+            s.push_str(&format!("\nMoves {}", arrows.map(String::from).join(" ")));
+            if let Some((index, direction, distance)) = self.settled_silence {
+                let letter = match direction {
+                    Direction::North => 'N',
+                    Direction::East => 'E',
+                    Direction::South => 'S',
+                    Direction::West => 'W',
+                };
+                s.push_str(&format!(
+                    " · silence {} was clearly {letter}{distance}, y to commit",
+                    index + 1
+                ));
+            }
+            if self.unchanged_for >= App::STALE_MOVES {
+                s.push_str(&format!(
+                    " · possibilities unchanged for {} moves, consider a drone",
+                    self.unchanged_for
+                ));
+            }
 
-            let text = Text::from(s + &instructions);
+            let text = Text::from(s + HELP_HINT);
             text.render(area, buf);
         } else {
-            let text = Text::from("No possible paths".to_string() + &instructions);
+            let reason = match self.empty_reason {
+                Some(EmptyReason::MapTooSmall) => {
+                    ": the trace is longer than any path on this map.\nUndo the last moves."
+                        .to_string()
+                }
+                Some(EmptyReason::ForcedSurface { index }) => format!(
+                    ": the moves stop fitting on the map at trace element {}.\nThe enemy must have surfaced before it.",
+                    index + 1
+                ),
+                Some(EmptyReason::ContradictoryIntel { index }) => format!(
+                    ": the intel at trace element {} contradicts everything else.\nIt was probably entered wrong.",
+                    index + 1
+                ),
+                Some(EmptyReason::OverConstrained) => {
+                    ": several pieces of intel contradict each other.\nUndo the latest intel."
+                        .to_string()
+                }
+                None => String::new(),
+            };
+            let text = Text::from(format!("No possible paths{reason}{HELP_HINT}"));
             text.render(area, buf);
         }
+
+        if self.show_help {
+            let width = KEYMAP.lines().map(|l| l.chars().count()).max().unwrap_or(0) as u16 + 4;
+            let height = KEYMAP.lines().count() as u16 + 2;
+            let overlay = centered(area, width, height);
+
+            Clear.render(overlay, buf);
+            Paragraph::new(KEYMAP)
+                .block(Block::bordered().title(" Keymap "))
+                .render(overlay, buf);
+        }
+    }
+}
+
+/// the keys picking each quadrant, with the board's names for them if they differ
+fn quadrant_keys(label: QuadrantLabel) -> String {
+    Quadrant::ALL
+        .map(|quadrant| match label {
+            QuadrantLabel::Numbers => quadrant.to_string(),
+            _ => format!("{quadrant} = {}", label.label(quadrant)),
+        })
+        .join(", ")
+}
+
+/// a rectangle of at most the given size in the middle of `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghost_keeps_the_trail_from_before_a_surface() {
+        let map = Map::new(10, HashSet::new());
+        let mut radar = Radar::new(map.clone());
+        for direction in [Direction::East, Direction::South, Direction::West] {
+            radar.register_move(Move::Directed(direction)).unwrap();
+        }
+        radar.register_move(Move::Surface).unwrap();
+        radar
+            .register_move(Move::Directed(Direction::North))
+            .unwrap();
+
+        let path = radar.get_possible_paths().next().unwrap();
+        let [origin, east, south_east, south] = [0, 1, 2, 3].map(|i| path[i].coord());
+
+        let ghost = path_glyphs(&map, &path, true);
+        assert_eq!(ghost(east), '~');
+        assert_eq!(ghost(south_east), '~');
+        assert_eq!(ghost(south), '*');
+        assert_eq!(ghost(origin), '*');
+
+        let no_ghost = path_glyphs(&map, &path, false);
+        assert_eq!(no_ghost(east), '.');
+        assert_eq!(no_ghost(origin), '*');
+    }
+
+    #[test]
+    fn map_edits_undo_and_redo_toggles() {
+        let (a, b) = (Coordinate::new(1, 1), Coordinate::new(2, 3));
+        let mut edits = MapEdits::default();
+        edits.record(a);
+        edits.record(b);
+
+        assert_eq!(edits.undo(), Some(b));
+        assert_eq!(edits.undo(), Some(a));
+        assert_eq!(edits.undo(), None);
+        assert_eq!(edits.redo(), Some(a));
+
+        edits.record(b);
+        assert_eq!(edits.redo(), None);
+        assert_eq!(edits.undo(), Some(b));
+    }
+
+    /// waits for the worker to deliver the paths of the latest request
+    fn settle(app: &mut App) {
+        for _ in 0..500 {
+            app.receive_possible_paths();
+            if !app.worker.is_computing() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the worker did not deliver the paths");
+    }
+
+    #[test]
+    fn drone_keys_add_quadrant_intel() {
+        let radar = Radar::new(Map::new(10, HashSet::new()));
+        let mut app = App::new(radar.clone());
+        settle(&mut app);
+        assert_eq!(app.snapshot().possible_paths, 100);
+        app.handle_key_code(KeyCode::Tab);
+        assert_eq!(app.snapshot().show_path_index, Some(1));
+
+        app.handle_key_code(KeyCode::Char('q'));
+        assert!(matches!(
+            app.snapshot().submenu,
+            Some(Submenu::IntelPickQuadrant { quadrant: None })
+        ));
+        app.handle_key_code(KeyCode::Char('2'));
+        assert!(matches!(
+            app.snapshot().submenu,
+            Some(Submenu::IntelPickQuadrant {
+                quadrant: Some(Quadrant::Two)
+            })
+        ));
+        app.handle_key_code(KeyCode::Char('y'));
+
+        settle(&mut app);
+        let snapshot = app.snapshot();
+        assert!(snapshot.submenu.is_none());
+        assert_eq!(snapshot.error, None);
+        assert_eq!(snapshot.possible_paths, 25);
+        assert_eq!(snapshot.show_path_index, Some(1));
+
+        let mut expected = radar;
+        expected
+            .add_intel(IntelQuestion::InQuadrant {
+                quadrant: Quadrant::Two,
+                answer: true,
+            })
+            .unwrap();
+        assert_eq!(app.radar.trace(), expected.trace());
+    }
+
+    #[test]
+    fn numeric_prefix_repeats_the_next_move() {
+        let map = Map::new(10, HashSet::new());
+        let mut app = App::new(Radar::new(map.clone()));
+        let moves = |app: &App| app.radar.trace().len();
+
+        app.handle_key_code(KeyCode::Char('3'));
+        assert_eq!(app.repeat, "3");
+        app.handle_key_code(KeyCode::Right);
+        assert_eq!(app.repeat, "");
+        assert_eq!(moves(&app), 3);
+        assert_eq!(app.snapshot().error, None);
+
+        // any other key drops the prefix
+        app.handle_key_code(KeyCode::Char('2'));
+        app.handle_key_code(KeyCode::Char('n'));
+        app.handle_key_code(KeyCode::Down);
+        assert_eq!(moves(&app), 4);
+
+        app.handle_key_code(KeyCode::Char('0'));
+        app.handle_key_code(KeyCode::Down);
+        assert_eq!(moves(&app), 4);
+        assert!(matches!(app.error, Some(AppError::EmptyBurst)));
+
+        // heading back north crosses the first moves after a single step
+        app.handle_key_code(KeyCode::Down);
+        app.handle_key_code(KeyCode::Char('2'));
+        app.handle_key_code(KeyCode::Left);
+        app.handle_key_code(KeyCode::Char('5'));
+        app.handle_key_code(KeyCode::Up);
+        assert_eq!(moves(&app), 8);
+        assert!(matches!(
+            app.error,
+            Some(AppError::Burst {
+                done: 1,
+                requested: 5
+            })
+        ));
+
+        let mut app = App::new(Radar::new(map));
+        app.handle_key_code(KeyCode::Char('1'));
+        app.handle_key_code(KeyCode::Char('2'));
+        app.handle_key_code(KeyCode::Down);
+        assert_eq!(moves(&app), 10);
+        assert!(matches!(
+            app.error,
+            Some(AppError::BurstClamped {
+                done: 10,
+                requested: 12
+            })
+        ));
+    }
+
+    #[test]
+    fn replay_ticks_advance_and_pause_at_the_end() {
+        let interval = Replay::DEFAULT_INTERVAL;
+        let mut replay = Replay::new(5);
+        assert!(!replay.tick(interval * 3));
+        assert_eq!(replay.until_next(), None);
+
+        replay.toggle_playing();
+        assert!(!replay.tick(interval / 2));
+        assert_eq!(replay.until_next(), Some(interval / 2));
+        // the leftover half counts towards the following steps
+        assert!(replay.tick(interval * 2));
+        assert_eq!(replay.step, 2);
+        assert_eq!(replay.until_next(), Some(interval / 2));
+
+        // stepping by hand restarts the wait
+        replay.step_by(1);
+        assert_eq!(replay.step, 3);
+        assert_eq!(replay.until_next(), Some(interval));
+
+        assert!(replay.tick(interval * 10));
+        assert_eq!(replay.step, 5);
+        assert!(!replay.playing);
+        assert!(!replay.tick(interval));
+
+        // playing from the end starts over
+        replay.toggle_playing();
+        assert_eq!(replay.step, 0);
+    }
+
+    #[test]
+    fn replay_speed_is_bounded() {
+        let mut replay = Replay::new(3);
+        replay.change_speed(false);
+        assert_eq!(replay.interval, Replay::DEFAULT_INTERVAL * 2);
+        for _ in 0..10 {
+            replay.change_speed(true);
+        }
+        assert_eq!(replay.interval, Replay::MIN_INTERVAL);
+        for _ in 0..10 {
+            replay.change_speed(false);
+        }
+        assert_eq!(replay.interval, Replay::MAX_INTERVAL);
+
+        replay.toggle_playing();
+        assert!(replay.tick(Replay::MAX_INTERVAL));
+        assert_eq!(replay.step, 1);
+        replay.step_by(-5);
+        assert_eq!(replay.step, 0);
+        replay.step_by(5);
+        assert_eq!(replay.step, 3);
+    }
+
+    #[test]
+    fn worker_only_delivers_the_newest_generation() {
+        let radar = Radar::new(Map::new(5, HashSet::new()));
+        let mut moved = radar.clone();
+        moved
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+
+        let mut worker = PathWorker::spawn();
+        assert!(!worker.is_computing());
+        worker.request(&radar);
+        worker.request(&moved);
+        assert!(worker.is_computing());
+        assert_eq!(worker.generation, 2);
+
+        let stale = PathResult {
+            generation: 1,
+            paths: vec![],
+            possibilities: PossibilitySet::default(),
+            empty_reason: None,
+            unchanged_for: 0,
+            settled_silence: None,
+        };
+        assert!(!worker.accepts(&stale));
+
+        let result = (0..500)
+            .find_map(|_| {
+                let result = worker.poll();
+                if result.is_none() {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                result
+            })
+            .expect("the worker did not deliver the paths");
+        assert_eq!(result.generation, 2);
+        assert_eq!(result.paths.len(), 20);
+        assert!(!worker.is_computing());
+        assert!(worker.poll().is_none());
+    }
+
+    #[test]
+    fn cone_grows_with_the_turns() {
+        let mut radar = Radar::new(Map::new(9, HashSet::new()));
+        radar
+            .add_intel(IntelQuestion::Cell {
+                coord: Coordinate::new(4, 4),
+                present: true,
+            })
+            .unwrap();
+        let mut app = App::new(radar);
+        settle(&mut app);
+
+        // adjusting does nothing while the overlay is off
+        app.handle_key_code(KeyCode::Char('+'));
+        assert_eq!(app.cone_turns, None);
+        assert!(app.cone.is_empty());
+
+        app.handle_key_code(KeyCode::Char('c'));
+        app.handle_key_code(KeyCode::Char('-'));
+        assert_eq!(app.cone_turns, Some(1));
+        // every cell within that many moves, except the position itself
+        for (turns, cells) in [(1, 4), (2, 4 + 8), (3, 4 + 8 + 12)] {
+            assert_eq!(app.cone_turns, Some(turns));
+            assert_eq!(app.cone.len(), cells, "{turns} turns");
+            assert!(app
+                .cone
+                .iter()
+                .all(|c| (1..=turns as u32).contains(&(c.x().abs_diff(4) + c.y().abs_diff(4)))));
+            app.handle_key_code(KeyCode::Char('+'));
+        }
+
+        for _ in 0..2 * Radar::MAX_REACHABLE_TURNS {
+            app.handle_key_code(KeyCode::Char('+'));
+        }
+        assert_eq!(app.cone_turns, Some(Radar::MAX_REACHABLE_TURNS));
+
+        app.handle_key_code(KeyCode::Char('c'));
+        assert_eq!(app.cone_turns, None);
+        assert!(app.cone.is_empty());
+    }
+
+    #[test]
+    fn confidence_lines_of_a_three_cell_distribution() {
+        let cell = |s: &str| s.parse::<Coordinate>().unwrap();
+        assert_eq!(
+            confidence_lines(&[(cell("e7"), 0.6), (cell("e6"), 0.3), (cell("f7"), 0.1)]),
+            ["e7: 60%", "e6: 30%", "f7: 10%"]
+        );
+
+        // thirds round to 34, 33 and 33, the first of the tied cells gets the spare point
+        let third = 1.0 / 3.0;
+        assert_eq!(
+            confidence_lines(&[
+                (cell("a1"), third),
+                (cell("b1"), third),
+                (cell("c1"), third)
+            ]),
+            ["a1: 34%", "b1: 33%", "c1: 33%"]
+        );
+
+        // weights are normalized
+        assert_eq!(
+            confidence_lines(&[(cell("a1"), 2.0), (cell("b1"), 1.0), (cell("c1"), 1.0)]),
+            ["a1: 50%", "b1: 25%", "c1: 25%"]
+        );
+        assert!(confidence_lines(&[]).is_empty());
+    }
+
+    #[test]
+    fn grid_style_and_view_fit_the_area() {
+        let area = |width, height| Rect::new(0, 0, width, height);
+
+        // a 10x10 map needs 33 columns and 21 + 4 rows spaced out
+        assert_eq!(GridStyle::for_area(area(33, 25), 10), GridStyle::Spacious);
+        assert_eq!(GridStyle::for_area(area(200, 100), 10), GridStyle::Spacious);
+        assert_eq!(GridStyle::for_area(area(32, 25), 10), GridStyle::Compact);
+        assert_eq!(GridStyle::for_area(area(33, 24), 10), GridStyle::Compact);
+        assert_eq!(GridStyle::for_area(area(0, 0), 10), GridStyle::Compact);
+
+        assert_eq!(GridStyle::max_cells(area(80, 24)), 17);
+        assert_eq!(GridStyle::max_cells(area(5, 100)), 2);
+        // at least a single cell is always shown
+        assert_eq!(GridStyle::max_cells(area(0, 0)), 1);
+
+        let cells = [Coordinate::new(5, 5), Coordinate::new(6, 5)];
+        assert_eq!(
+            Viewport::around(cells.into_iter(), 10, 2, 17),
+            Viewport {
+                x: 3,
+                y: 3,
+                width: 6,
+                height: 5
+            }
+        );
+        assert_eq!(
+            Viewport::around(cells.into_iter(), 10, 2, 1),
+            Viewport {
+                x: 5,
+                y: 5,
+                width: 1,
+                height: 1
+            }
+        );
+        assert_eq!(
+            Viewport::around([].into_iter(), 10, 2, 1),
+            Viewport::full(10)
+        );
+    }
+
+    #[test]
+    fn rendering_into_tiny_areas_does_not_panic() {
+        let mut app = App::new(Radar::new(Map::new(10, HashSet::new())));
+        settle(&mut app);
+        for follow in [false, true] {
+            app.follow = follow;
+            for (width, height) in [(0, 0), (1, 1), (0, 30), (30, 0), (12, 6), (40, 12)] {
+                let area = Rect::new(0, 0, width, height);
+                let mut buf = Buffer::empty(area);
+                (&mut app).render(area, &mut buf);
+            }
+        }
     }
 }
@@ -0,0 +1,268 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::radar::{parse_log, Coordinate, LogError, Map, MapBuilder, MapError, Radar, Trace};
+
+#[derive(Debug, Error)]
+pub enum MapFileError {
+    #[cfg(feature = "std")]
+    #[error("Could not read the map file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The map file does not declare a size")]
+    MissingSize,
+    #[error("Line {line}: invalid map size {size:?}")]
+    InvalidSize { line: usize, size: String },
+    #[error("Line {line}: {source}")]
+    Map { line: usize, source: MapError },
+    #[error("Line {line}: expected {expected} cells to keep the map square, found {found}")]
+    RowLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Line {line}: unknown cell {cell:?}, expected '#' or '.'")]
+    UnknownCell { line: usize, cell: char },
+}
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("The game log needs a blank line between the map and the moves")]
+    MissingLog,
+    #[error("Invalid map: {0}")]
+    Map(#[from] MapFileError),
+    #[error("Invalid moves: {0}")]
+    Log(#[from] LogError),
+}
+
+/// Imports a recorded game: the map in the grid format of [`Map::from_ascii`], a blank line,
+/// then the moves and intel in the format of [`parse_log`] over any number of lines.
+pub fn import_game_log(log: &str) -> Result<(Map, Trace), ImportError> {
+    let log = log.trim_start().replace("\r\n", "\n");
+    let (grid, moves) = log.split_once("\n\n").ok_or(ImportError::MissingLog)?;
+
+    let map = Map::from_ascii(grid)?;
+    let mut radar = Radar::new(map.clone());
+    radar.register_log(moves)?;
+    Ok((map, radar.trace().clone()))
+}
+
+/// Parses a map shared as text: the map size on the first line, then one obstacle per line
+/// in board notation (eg. `b3`). Everything after a `#` is a comment, blank lines are ignored.
+pub fn from_coords_str(contents: &str) -> Result<Map, MapFileError> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or("").trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (line, size) = lines.next().ok_or(MapFileError::MissingSize)?;
+    let size = match size.parse::<u32>() {
+        Ok(size) if size > 0 => size,
+        _ => {
+            return Err(MapFileError::InvalidSize {
+                line,
+                size: size.to_string(),
+            })
+        }
+    };
+
+    let mut builder = MapBuilder::new(size);
+    for (line, coord) in lines {
+        let coord = coord.parse::<Coordinate>().map_err(|e| MapFileError::Map {
+            line,
+            source: e.into(),
+        })?;
+        if coord.x() >= size || coord.y() >= size {
+            return Err(MapFileError::Map {
+                line,
+                source: MapError::ObstacleOutOfBounds(coord),
+            });
+        }
+        builder = builder.obstacle(coord);
+    }
+
+    Ok(builder
+        .build()
+        .expect("obstacles were checked to be on the map"))
+}
+
+impl Map {
+    /// Parses a grid of `#` for obstacles and `.` for water, one row per line.
+    /// The map is square, so there are as many rows as cells in each row. Blank lines are ignored.
+    pub fn from_ascii(grid: &str) -> Result<Self, MapFileError> {
+        let rows = grid
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .collect::<Vec<_>>();
+        if rows.is_empty() {
+            return Err(MapFileError::MissingSize);
+        }
+
+        let mut builder = MapBuilder::new(rows.len() as u32);
+        for (y, &(line, row)) in rows.iter().enumerate() {
+            let found = row.chars().count();
+            if found != rows.len() {
+                return Err(MapFileError::RowLength {
+                    line,
+                    expected: rows.len(),
+                    found,
+                });
+            }
+
+            for (x, cell) in row.chars().enumerate() {
+                match cell {
+                    '#' => builder = builder.obstacle(Coordinate::new(x as u32, y as u32)),
+                    '.' => (),
+                    _ => return Err(MapFileError::UnknownCell { line, cell }),
+                }
+            }
+        }
+
+        Ok(builder.build().expect("obstacles were placed on the map"))
+    }
+
+    /// Formats the map in the grid format of [`Map::from_ascii`].
+    /// The grid only holds the size and obstacles, so reading it back gives the default
+    /// topology and quadrant labels rather than this map's.
+    pub fn to_ascii(&self) -> String {
+        let mut grid = String::new();
+        for y in 0..self.size() {
+            for x in 0..self.size() {
+                grid.push(if self.obstacles().contains(&Coordinate::new(x, y)) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            grid.push('\n');
+        }
+        grid
+    }
+}
+
+/// Reads a map from a coordinates file, see [`from_coords_str`] for the format.
+#[cfg(feature = "std")]
+pub fn from_coords_file(path: impl AsRef<std::path::Path>) -> Result<Map, MapFileError> {
+    from_coords_str(&std::fs::read_to_string(path)?)
+}
+
+/// Ranks maps by how well they fit a move log, to find out which board is played.
+///
+/// Returns map indices with their possible position counts after the log (see [`parse_log`]),
+/// fewest first. Maps where the log is impossible have a count of 0 and come last.
+pub fn rank_maps(maps: &[Map], log: &str) -> Result<Vec<(usize, usize)>, LogError> {
+    // reject malformed logs up front rather than ranking every map as impossible
+    parse_log(log)?;
+
+    let mut ranking = maps
+        .iter()
+        .enumerate()
+        .map(|(i, map)| {
+            let mut radar = Radar::new(map.clone());
+            match radar.register_log(log) {
+                Ok(()) => (i, radar.count_possible_positions()),
+                Err(_) => (i, 0),
+            }
+        })
+        .collect::<Vec<_>>();
+    ranking.sort_by_key(|&(i, count)| (count == 0, count, i));
+
+    Ok(ranking)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        intel::{InformationPiece, IntelQuestion, Quadrant, QuadrantLabel},
+        radar::{Direction, MapTopology, Move},
+        scenario::Scenario,
+    };
+
+    #[test]
+    fn ascii_grid_round_trips_random_maps() {
+        for seed in 0..64 {
+            let (map, _, _) = Scenario::generate(seed);
+            assert_eq!(
+                Map::from_ascii(&map.to_ascii()).unwrap(),
+                map,
+                "seed {seed}"
+            );
+        }
+        let single = MapBuilder::new(1)
+            .obstacle(Coordinate::new(0, 0))
+            .build()
+            .unwrap();
+        assert_eq!(Map::from_ascii(&single.to_ascii()).unwrap(), single);
+    }
+
+    #[test]
+    fn ascii_grid_drops_topology_and_labels() {
+        let map = MapBuilder::new(3)
+            .obstacle(Coordinate::new(1, 2))
+            .topology(MapTopology::Toroidal)
+            .quadrant_label(QuadrantLabel::Letters)
+            .build()
+            .unwrap();
+        let read = Map::from_ascii(&map.to_ascii()).unwrap();
+        assert_eq!(read.obstacles(), map.obstacles());
+        assert_eq!(read.topology(), MapTopology::default());
+        assert_eq!(read.quadrant_label(), QuadrantLabel::default());
+    }
+
+    #[test]
+    fn imports_map_moves_and_intel() {
+        let log = "
+....
+.#..
+....
+....
+
+n e silence
+drone:2:no, sonar:r3:cb
+surface mine
+";
+        let (map, trace) = import_game_log(log).unwrap();
+        assert_eq!(map.size(), 4);
+        assert!(map.obstacles().contains(&Coordinate::new(1, 1)));
+
+        let mut expected = Radar::new(map);
+        expected
+            .register_move(Move::Directed(Direction::North))
+            .unwrap();
+        expected
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        expected.register_move(Move::Dash).unwrap();
+        expected
+            .add_intel(IntelQuestion::InQuadrant {
+                quadrant: Quadrant::Two,
+                answer: false,
+            })
+            .unwrap();
+        expected
+            .add_intel(IntelQuestion::TruthLie {
+                info1: InformationPiece::Row(2),
+                info2: InformationPiece::Column(1),
+            })
+            .unwrap();
+        expected.register_move(Move::Surface).unwrap();
+        expected.plant_mine();
+        assert_eq!(trace, *expected.trace());
+    }
+
+    #[test]
+    fn rejects_unknown_entries() {
+        let log = "..\n..\n\nn drone:5:yes\n";
+        assert!(matches!(
+            import_game_log(log),
+            Err(ImportError::Log(LogError::UnknownEntry(_)))
+        ));
+    }
+}
@@ -1,30 +1,62 @@
-use std::{collections::HashSet, ops::Add};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Range},
+    str::FromStr,
+};
 
 use thiserror::Error;
 
-use crate::intel::{InformationPiece, IntelQuestion, Quadrant};
+use crate::{
+    analysis::{EmptyReason, PossibilitySet},
+    collections::{HashMap, HashSet},
+    intel::{Axis, InformationPiece, IntelQuestion, Quadrant, QuadrantLabel, Side},
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coordinate {
     x: u32,
     y: u32,
 }
 
 impl Coordinate {
+    const NEIGHBOUR_OFFSETS: [Offset; 8] = [
+        Offset::new(-1, -1),
+        Offset::new(0, -1),
+        Offset::new(1, -1),
+        Offset::new(-1, 0),
+        Offset::new(1, 0),
+        Offset::new(-1, 1),
+        Offset::new(0, 1),
+        Offset::new(1, 1),
+    ];
+
+    /// The 8 surrounding coordinates, not bounded by any map. See [`Map::neighbours_8`].
     pub fn neighbours(&self) -> impl Iterator<Item = Self> {
-        let origin: Offset = (*self).into();
-        [
-            Offset::new(-1, -1),
-            Offset::new(0, -1),
-            Offset::new(1, -1),
-            Offset::new(-1, 0),
-            Offset::new(1, 0),
-            Offset::new(-1, 1),
-            Offset::new(0, 1),
-            Offset::new(1, 1),
-        ]
-        .into_iter()
-        .filter_map(move |o| Self::try_from(origin + o).ok())
+        let origin = *self;
+        Self::NEIGHBOUR_OFFSETS
+            .into_iter()
+            .filter_map(move |o| origin + o)
+    }
+}
+
+/// Row-major, ie. reading order: by row first, then by column.
+impl Ord for Coordinate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+}
+
+impl PartialOrd for Coordinate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -32,23 +64,39 @@ impl TryFrom<Offset> for Coordinate {
     type Error = ();
 
     fn try_from(value: Offset) -> Result<Self, Self::Error> {
-        if value.x < 0 || value.y < 0 {
-            Err(())
-        } else {
-            Ok(Self::new(value.x as u32, value.y as u32))
+        match (u32::try_from(value.x), u32::try_from(value.y)) {
+            (Ok(x), Ok(y)) => Ok(Self::new(x, y)),
+            _ => Err(()),
         }
     }
 }
 
+/// Moves the coordinate by the offset. Returns `None` if the result would leave the non-negative range.
+impl Add<Offset> for Coordinate {
+    type Output = Option<Self>;
+
+    fn add(self, rhs: Offset) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_add_signed(rhs.x)?,
+            self.y.checked_add_signed(rhs.y)?,
+        ))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Offset {
     x: i32,
     y: i32,
 }
 
-impl From<Coordinate> for Offset {
-    fn from(value: Coordinate) -> Self {
-        Self::new(value.x as i32, value.y as i32)
+impl TryFrom<Coordinate> for Offset {
+    type Error = ();
+
+    fn try_from(value: Coordinate) -> Result<Self, Self::Error> {
+        match (i32::try_from(value.x), i32::try_from(value.y)) {
+            (Ok(x), Ok(y)) => Ok(Self::new(x, y)),
+            _ => Err(()),
+        }
     }
 }
 
@@ -58,8 +106,27 @@ impl Offset {
     const fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    /// Displacement towards the east.
+    pub const fn x(&self) -> i32 {
+        self.x
+    }
+
+    /// Displacement towards the south.
+    pub const fn y(&self) -> i32 {
+        self.y
+    }
+
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match (self.x.checked_add(rhs.x), self.y.checked_add(rhs.y)) {
+            (Some(x), Some(y)) => Some(Self::new(x, y)),
+            _ => None,
+        }
+    }
 }
 
+/// Trace offsets grow by at most one cell per trace element, so they stay far away from the
+/// integer limits. Use [`Offset::checked_add`] for arbitrary offsets.
 impl Add<Self> for Offset {
     type Output = Self;
 
@@ -72,12 +139,74 @@ impl Coordinate {
     pub const fn new(x: u32, y: u32) -> Self {
         Self { x, y }
     }
+
+    pub const fn x(&self) -> u32 {
+        self.x
+    }
+
+    pub const fn y(&self) -> u32 {
+        self.y
+    }
+}
+
+/// Formats as the board notation, eg. `b4` for column 1 and row 3.
+impl Display for Coordinate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.x.checked_add(10).and_then(|d| char::from_digit(d, 36)) {
+            Some(column) if self.y < u32::MAX => write!(f, "{}{}", column, self.y + 1),
+            _ => write!(f, "({}, {})", self.x, self.y),
+        }
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ParseCoordinateError {
+    #[error("Expected a column letter, got {0:?}")]
+    Column(String),
+    #[error("Expected a row number starting at 1, got {0:?}")]
+    Row(String),
+}
+
+impl FromStr for Coordinate {
+    type Err = ParseCoordinateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let x = match chars.next() {
+            Some(c @ 'a'..='z') => c as u32 - 'a' as u32,
+            Some(c @ 'A'..='Z') => c as u32 - 'A' as u32,
+            _ => return Err(ParseCoordinateError::Column(s.to_string())),
+        };
+
+        let row = chars.as_str();
+        match row.parse::<u32>() {
+            Ok(y) if y >= 1 => Ok(Self::new(x, y - 1)),
+            _ => Err(ParseCoordinateError::Row(row.to_string())),
+        }
+    }
+}
+
+/// What happens at the edges of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MapTopology {
+    /// Edges are walls, as in the standard game.
+    #[default]
+    Bounded,
+    /// Leaving the board on one edge re-enters it on the opposite one.
+    Toroidal,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Map {
     size: u32,
     obstacles: HashSet<Coordinate>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    topology: MapTopology,
+    #[cfg_attr(feature = "serde", serde(default))]
+    quadrant_label: QuadrantLabel,
 }
 
 impl Map {
@@ -87,7 +216,51 @@ impl Map {
             "Obstacle out of bounds"
         );
 
-        Self { size, obstacles }
+        Self {
+            size,
+            obstacles,
+            topology: MapTopology::Bounded,
+            quadrant_label: QuadrantLabel::Numbers,
+        }
+    }
+
+    pub const fn with_quadrant_label(mut self, quadrant_label: QuadrantLabel) -> Self {
+        self.quadrant_label = quadrant_label;
+        self
+    }
+
+    /// How quadrants of this board are named to the players.
+    pub const fn quadrant_label(&self) -> QuadrantLabel {
+        self.quadrant_label
+    }
+
+    pub const fn with_topology(mut self, topology: MapTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub const fn topology(&self) -> MapTopology {
+        self.topology
+    }
+
+    /// The coordinate `offset` away from `coord`, wrapping around in [`MapTopology::Toroidal`] maps.
+    /// `None` if it falls off a [`MapTopology::Bounded`] map.
+    pub fn translate(&self, coord: Coordinate, offset: Offset) -> Option<Coordinate> {
+        match self.topology {
+            MapTopology::Bounded => (coord + offset).filter(|&c| self.contains(c)),
+            MapTopology::Toroidal => {
+                if !self.contains(coord) {
+                    return None;
+                }
+                let size = i64::from(self.size);
+                let wrap =
+                    |at: u32, by: i32| (i64::from(at) + i64::from(by)).rem_euclid(size) as u32;
+                Some(Coordinate::new(
+                    wrap(coord.x, offset.x),
+                    wrap(coord.y, offset.y),
+                ))
+            }
+        }
     }
 
     pub const fn contains(&self, coord: Coordinate) -> bool {
@@ -107,6 +280,74 @@ impl Map {
         })
     }
 
+    /// Places a relative path at `origin`, or `None` if any step of it is off the board.
+    /// On a [`MapTopology::Toroidal`] map it is also `None` if the path wraps onto itself.
+    pub fn absolutize(
+        &self,
+        path: &[OffsetWithIntel],
+        origin: Coordinate,
+    ) -> Option<Vec<Coordinate>> {
+        let coords = path
+            .iter()
+            .map(|p| self.translate(origin, p.offset))
+            .collect::<Option<Vec<_>>>()?;
+
+        match self.topology {
+            MapTopology::Bounded => Some(coords),
+            MapTopology::Toroidal => {
+                // wrapping around must not land on the trail, which a surface wipes
+                let mut trail = HashSet::new();
+                for (p, &coord) in path.iter().zip(&coords) {
+                    if p.surfaced {
+                        trail.clear();
+                    }
+                    if !trail.insert(coord) {
+                        return None;
+                    }
+                }
+                Some(coords)
+            }
+        }
+    }
+
+    /// Columns and rows of the origins from which every offset stays on the board.
+    /// On a toroidal map that is every cell.
+    fn fitting_origins(
+        &self,
+        offsets: impl IntoIterator<Item = Offset>,
+    ) -> (Range<u32>, Range<u32>) {
+        if self.topology == MapTopology::Toroidal {
+            return (0..self.size, 0..self.size);
+        }
+
+        let (mut min, mut max) = (Offset::ZERO, Offset::ZERO);
+        for offset in offsets {
+            min = Offset::new(min.x.min(offset.x), min.y.min(offset.y));
+            max = Offset::new(max.x.max(offset.x), max.y.max(offset.y));
+        }
+
+        let range = |min: i32, max: i32| {
+            let start = min.unsigned_abs();
+            let end = (i64::from(self.size) - i64::from(max)).max(i64::from(start)) as u32;
+            start..end
+        };
+        (range(min.x, max.x), range(min.y, max.y))
+    }
+
+    /// Open cells of a quadrant in reading order, consistent with [`Map::quadrant_of`].
+    pub fn quadrant_cells(&self, quadrant: Quadrant) -> impl Iterator<Item = Coordinate> + use<'_> {
+        let half = self.size / 2;
+        let (xs, ys) = match quadrant {
+            Quadrant::One => (0..half, 0..half),
+            Quadrant::Two => (half..self.size, 0..half),
+            Quadrant::Three => (0..half, half..self.size),
+            Quadrant::Four => (half..self.size, half..self.size),
+        };
+
+        ys.flat_map(move |y| xs.clone().map(move |x| Coordinate::new(x, y)))
+            .filter(|&c| self.is_open(c))
+    }
+
     pub const fn size(&self) -> u32 {
         self.size
     }
@@ -114,9 +355,223 @@ impl Map {
     pub const fn obstacles(&self) -> &HashSet<Coordinate> {
         &self.obstacles
     }
+
+    /// Turns water at `coord` into an obstacle or the other way around.
+    /// Returns whether there is an obstacle now.
+    pub fn toggle_obstacle(&mut self, coord: Coordinate) -> Result<bool, MapError> {
+        if !self.contains(coord) {
+            return Err(MapError::ObstacleOutOfBounds(coord));
+        }
+
+        if self.obstacles.remove(&coord) {
+            Ok(false)
+        } else {
+            self.obstacles.insert(coord);
+            Ok(true)
+        }
+    }
+
+    /// Number of cells that are not obstacles.
+    pub fn open_count(&self) -> usize {
+        (self.size as usize).pow(2) - self.obstacles.len()
+    }
+
+    /// Whether the enemy being at `coord` agrees with the intel.
+    fn intel_holds(&self, intel: &IntelQuestion, coord: Coordinate) -> bool {
+        let Some(quadrant) = self.quadrant_of(coord) else {
+            return false;
+        };
+
+        match intel {
+            IntelQuestion::InQuadrant {
+                quadrant: question_quadrant,
+                answer,
+            }
+            | IntelQuestion::SurfacedInQuadrant {
+                quadrant: question_quadrant,
+                answer,
+            } => (quadrant == *question_quadrant) == *answer,
+            IntelQuestion::NotInQuadrants { quadrants } => !quadrants.contains(&quadrant),
+            &IntelQuestion::LineHit {
+                from,
+                direction,
+                range,
+            } => self.ray(from, direction, range).contains(&coord),
+            &IntelQuestion::Half { axis, side } => {
+                let value = match axis {
+                    Axis::Horizontal => coord.y,
+                    Axis::Vertical => coord.x,
+                };
+                let start = value < self.size / 2;
+                start == (side == Side::Start)
+            }
+            &IntelQuestion::SharesRowWith(row) => coord.y == row,
+            &IntelQuestion::SharesColumnWith(column) => coord.x == column,
+            &IntelQuestion::Cell {
+                coord: cell,
+                present,
+            } => (coord == cell) == present,
+            &IntelQuestion::TruthLie { info1, info2 } => {
+                // exactly one of them is true
+                self.information_holds(coord, info1) != self.information_holds(coord, info2)
+            }
+        }
+    }
+
+    /// Whether a piece of sonar information is true for the enemy being at `coord`.
+    /// Rows and columns are 0-based, like [`Coordinate`].
+    fn information_holds(&self, coord: Coordinate, piece: InformationPiece) -> bool {
+        match piece {
+            InformationPiece::Quadrant(quadrant) => self.quadrant_of(coord) == Some(quadrant),
+            InformationPiece::Column(column) => coord.x == column,
+            InformationPiece::Row(row) => coord.y == row,
+            InformationPiece::OutsideQuadrant(_)
+            | InformationPiece::OutsideColumn(_)
+            | InformationPiece::OutsideRow(_) => !self.information_holds(coord, piece.negated()),
+        }
+    }
+
+    /// Every open cell where the enemy could be according to `intel` alone, ignoring any trace.
+    pub fn cells_satisfying(&self, intel: &IntelQuestion) -> HashSet<Coordinate> {
+        (0..self.size)
+            .flat_map(|y| (0..self.size).map(move |x| Coordinate::new(x, y)))
+            .filter(|&cell| self.is_open(cell) && self.intel_holds(intel, cell))
+            .collect()
+    }
+
+    /// Whether a submarine can be at the coordinate, ie. it is on the map and not an obstacle.
+    pub fn is_open(&self, coord: Coordinate) -> bool {
+        self.contains(coord) && !self.obstacles.contains(&coord)
+    }
+
+    /// Orthogonal neighbours of `coord` that are on the map, obstacles included.
+    pub fn neighbours(&self, coord: Coordinate) -> impl Iterator<Item = Coordinate> + use<'_> {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |direction| self.translate(coord, direction.delta()))
+    }
+
+    /// Orthogonal and diagonal neighbours of `coord` that are on the map, obstacles included.
+    pub fn neighbours_8(&self, coord: Coordinate) -> impl Iterator<Item = Coordinate> + use<'_> {
+        Coordinate::NEIGHBOUR_OFFSETS
+            .into_iter()
+            .filter_map(move |o| self.translate(coord, o))
+    }
+
+    /// The coordinate reached by moving once in `direction`, if it is on the map and not an obstacle.
+    pub fn step(&self, coord: Coordinate, direction: Direction) -> Option<Coordinate> {
+        let next = self.translate(coord, direction.delta())?;
+        self.is_open(next).then_some(next)
+    }
+
+    /// Cells along a straight line from `from` (excluded), until the map edge,
+    /// the first obstacle or `range` cells, whichever comes first.
+    pub fn ray(&self, from: Coordinate, direction: Direction, range: u32) -> Vec<Coordinate> {
+        let mut ray = vec![];
+        let mut at = from;
+        for _ in 0..range {
+            let Some(next) = self.step(at, direction) else {
+                break;
+            };
+            ray.push(next);
+            at = next;
+        }
+        ray
+    }
+
+    /// The [`Map::ray`] in each direction, in the order of [`Direction::ALL`].
+    pub fn firing_lines(&self, from: Coordinate, range: u32) -> Vec<Vec<Coordinate>> {
+        Direction::ALL
+            .iter()
+            .map(|&direction| self.ray(from, direction, range))
+            .collect()
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum MapError {
+    #[error("Obstacle {0} is out of bounds")]
+    ObstacleOutOfBounds(Coordinate),
+    #[error("Invalid obstacle: {0}")]
+    InvalidCoordinate(#[from] ParseCoordinateError),
+}
+
+/// Incrementally assembles a [`Map`], validating obstacles on [`MapBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct MapBuilder {
+    size: u32,
+    obstacles: HashSet<Coordinate>,
+    topology: MapTopology,
+    quadrant_label: QuadrantLabel,
+    error: Option<MapError>,
+}
+
+impl MapBuilder {
+    pub fn new(size: u32) -> Self {
+        Self {
+            size,
+            obstacles: HashSet::new(),
+            topology: MapTopology::Bounded,
+            quadrant_label: QuadrantLabel::Numbers,
+            error: None,
+        }
+    }
+
+    pub const fn quadrant_label(mut self, quadrant_label: QuadrantLabel) -> Self {
+        self.quadrant_label = quadrant_label;
+        self
+    }
+
+    pub const fn topology(mut self, topology: MapTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn obstacle(mut self, coord: Coordinate) -> Self {
+        self.obstacles.insert(coord);
+        self
+    }
+
+    pub fn obstacles(mut self, coords: impl IntoIterator<Item = Coordinate>) -> Self {
+        self.obstacles.extend(coords);
+        self
+    }
+
+    /// Adds an obstacle in board notation, eg. `b3`.
+    pub fn obstacle_at(mut self, coord: &str) -> Self {
+        match coord.parse() {
+            Ok(coord) => self.obstacle(coord),
+            Err(e) => {
+                self.error.get_or_insert_with(|| e.into());
+                self
+            }
+        }
+    }
+
+    pub fn build(self) -> Result<Map, MapError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        if let Some(&coord) = self
+            .obstacles
+            .iter()
+            .find(|c| c.x >= self.size || c.y >= self.size)
+        {
+            return Err(MapError::ObstacleOutOfBounds(coord));
+        }
+
+        Ok(Map {
+            size: self.size,
+            obstacles: self.obstacles,
+            topology: self.topology,
+            quadrant_label: self.quadrant_label,
+        })
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     North,
     East,
@@ -125,6 +580,8 @@ pub enum Direction {
 }
 
 impl Direction {
+    pub const ALL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
     const fn delta(&self) -> Offset {
         match self {
             Self::North => Offset::new(0, -1),
@@ -135,28 +592,176 @@ impl Direction {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Move {
     Directed(Direction),
     Dash,
+    /// A silence known not to have gone in any of these directions
+    DashExcluding(Vec<Direction>),
+    /// Surfacing in place, which wipes the trail so that it may be crossed again
+    Surface,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TraceElement {
     Move(Move),
     Intel(IntelQuestion),
     Mine,
 }
 
-#[derive(Debug)]
+/// Systems whose use shows up in the trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SystemKind {
+    Drone,
+    Sonar,
+    Mine,
+    Silence,
+    Torpedo,
+}
+
+impl SystemKind {
+    const fn used_in(self, element: &TraceElement) -> bool {
+        matches!(
+            (self, element),
+            (
+                Self::Drone,
+                TraceElement::Intel(
+                    IntelQuestion::InQuadrant { .. } | IntelQuestion::NotInQuadrants { .. }
+                )
+            ) | (
+                Self::Sonar,
+                TraceElement::Intel(IntelQuestion::TruthLie { .. })
+            ) | (Self::Mine, TraceElement::Mine)
+                | (
+                    Self::Silence,
+                    TraceElement::Move(Move::Dash | Move::DashExcluding(_))
+                )
+                | (
+                    Self::Torpedo,
+                    TraceElement::Intel(IntelQuestion::LineHit { .. })
+                )
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Trace {
     trace: Vec<TraceElement>,
+    /// Maximum distance travelled in a single silence
+    #[cfg_attr(feature = "serde", serde(default = "Trace::default_silence_range"))]
+    silence_range: u8,
+    /// Relative likelihood of each silence distance, indexed by distance. Missing entries weigh 1.
+    #[cfg_attr(feature = "serde", serde(default))]
+    silence_weights: Vec<f64>,
 }
 
 #[derive(Debug, Error)]
 pub enum TraceMoveError {
     #[error("The move would intersect the path")]
     SelfIntersect,
+    #[error("A silence has to leave at least one direction open")]
+    AllDirectionsExcluded,
+    #[error("Trace element {} is not a silence", .0 + 1)]
+    NotASilence(usize),
+    #[error("No position is possible, fix the intel before registering more moves")]
+    NoFeasiblePosition,
+}
+
+#[derive(Debug, Error)]
+pub enum IntelError {
+    #[error("The intel contradicts earlier intel")]
+    Contradiction,
+    #[error("There is no move number {0} in the trace")]
+    NoSuchStep(usize),
+    #[error("{0:?} lies outside of the map")]
+    PieceOutOfRange(InformationPiece),
+    #[error("{0:?} lies outside of the map")]
+    CoordinateOutOfRange(Coordinate),
+}
+
+#[derive(Debug, Error)]
+pub enum LogError {
+    #[error("Unknown log entry {0:?}")]
+    UnknownEntry(String),
+    #[error("Entry {index} of the log: {source}")]
+    Move {
+        index: usize,
+        source: TraceMoveError,
+    },
+}
+
+/// Parses a move log: entries separated by whitespace or commas, `n`, `e`, `s` and `w`
+/// (or the full direction names) for moves, `silence` (or `dash`), `surface` and `mine`. Case is ignored.
+///
+/// Drones are written `drone:<quadrant>:<yes|no>`, eg. `drone:2:no`, and sonars `sonar:<piece>:<piece>`
+/// with pieces `q<quadrant>`, `r<row>` or `c<column>` in board notation, eg. `sonar:r3:cb`.
+pub fn parse_log(log: &str) -> Result<Vec<TraceElement>, LogError> {
+    log.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            Ok(match entry.to_lowercase().as_str() {
+                "n" | "north" => TraceElement::Move(Move::Directed(Direction::North)),
+                "e" | "east" => TraceElement::Move(Move::Directed(Direction::East)),
+                "s" | "south" => TraceElement::Move(Move::Directed(Direction::South)),
+                "w" | "west" => TraceElement::Move(Move::Directed(Direction::West)),
+                "silence" | "dash" => TraceElement::Move(Move::Dash),
+                "surface" => TraceElement::Move(Move::Surface),
+                "mine" => TraceElement::Mine,
+                other => match parse_intel_entry(other) {
+                    Some(intel) => TraceElement::Intel(intel),
+                    None => return Err(LogError::UnknownEntry(entry.to_string())),
+                },
+            })
+        })
+        .collect()
+}
+
+/// A lowercase drone or sonar entry of a move log, see [`parse_log`].
+fn parse_intel_entry(entry: &str) -> Option<IntelQuestion> {
+    let quadrant = |s: &str| match s {
+        "1" => Some(Quadrant::One),
+        "2" => Some(Quadrant::Two),
+        "3" => Some(Quadrant::Three),
+        "4" => Some(Quadrant::Four),
+        _ => None,
+    };
+    let piece = |s: &str| {
+        let (kind, value) = s.split_at_checked(1)?;
+        match kind {
+            "q" => quadrant(value).map(InformationPiece::Quadrant),
+            "r" => match value.parse::<u32>() {
+                Ok(row) if row >= 1 => Some(InformationPiece::Row(row - 1)),
+                _ => None,
+            },
+            "c" => match value.as_bytes() {
+                &[column @ b'a'..=b'z'] => Some(InformationPiece::Column(u32::from(column - b'a'))),
+                _ => None,
+            },
+            _ => None,
+        }
+    };
+
+    let mut parts = entry.split(':');
+    let intel = match (parts.next()?, parts.next()?, parts.next()?) {
+        ("drone", q, answer) => IntelQuestion::InQuadrant {
+            quadrant: quadrant(q)?,
+            answer: match answer {
+                "yes" => true,
+                "no" => false,
+                _ => return None,
+            },
+        },
+        ("sonar", info1, info2) => IntelQuestion::TruthLie {
+            info1: piece(info1)?,
+            info2: piece(info2)?,
+        },
+        _ => return None,
+    };
+    parts.next().is_none().then_some(intel)
 }
 
 #[derive(Debug, Clone)]
@@ -164,28 +769,33 @@ pub struct OffsetWithIntel {
     offset: Offset,
     intel: Vec<IntelQuestion>,
     has_mine: bool,
+    /// the submarine surfaced here, the trail before it is wiped
+    surfaced: bool,
 }
 
 impl Trace {
+    pub const DEFAULT_SILENCE_RANGE: u8 = 4;
+
     const fn new() -> Self {
-        Self { trace: Vec::new() }
+        Self {
+            trace: Vec::new(),
+            silence_range: Self::DEFAULT_SILENCE_RANGE,
+            silence_weights: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    const fn default_silence_range() -> u8 {
+        Self::DEFAULT_SILENCE_RANGE
     }
 
     fn make_move(&mut self, r#move: Move) -> Result<(), TraceMoveError> {
         match r#move {
             Move::Directed(direction) => {
-                let all_self_intersects = self.paths().iter().all(|path| {
-                    if let Some(last) = path.last() {
-                        if path
-                            .iter()
-                            .any(|p| p.offset == (last.offset + direction.delta()))
-                        {
-                            return true;
-                        }
-                    }
-
-                    false
-                });
+                let all_self_intersects = self
+                    .paths()
+                    .iter()
+                    .all(|path| Self::self_intersects(path, direction));
 
                 if all_self_intersects {
                     return Err(TraceMoveError::SelfIntersect);
@@ -199,9 +809,84 @@ impl Trace {
                 self.trace.push(TraceElement::Move(Move::Dash));
                 Ok(())
             }
+            Move::DashExcluding(excluded) => {
+                if Direction::ALL.iter().all(|d| excluded.contains(d)) {
+                    return Err(TraceMoveError::AllDirectionsExcluded);
+                }
+
+                self.trace
+                    .push(TraceElement::Move(Move::DashExcluding(excluded)));
+                Ok(())
+            }
+            Move::Surface => {
+                self.trace.push(TraceElement::Move(Move::Surface));
+                Ok(())
+            }
         }
     }
 
+    /// The part of `path` since the last surface, which is all that a move must not cross.
+    fn trail(path: &[OffsetWithIntel]) -> &[OffsetWithIntel] {
+        let start = path.iter().rposition(|p| p.surfaced).unwrap_or(0);
+        &path[start..]
+    }
+
+    /// Whether moving once in `direction` from the end of `path` would cross its trail.
+    fn self_intersects(path: &[OffsetWithIntel], direction: Direction) -> bool {
+        path.last().is_some_and(|last| {
+            Self::trail(path)
+                .iter()
+                .any(|p| p.offset == (last.offset + direction.delta()))
+        })
+    }
+
+    /// Directions in which a silence could not move even a single cell on any path.
+    fn silence_blocked_directions(&self) -> HashSet<Direction> {
+        let paths = self.paths();
+
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| {
+                self.silence_range == 0
+                    || paths
+                        .iter()
+                        .all(|path| Self::self_intersects(path, direction))
+            })
+            .collect()
+    }
+
+    /// Fewest distinct cells some path of this trace visits without surfacing,
+    /// as silences may cover no distance.
+    fn min_path_len(&self) -> usize {
+        self.trace
+            .split(|element| matches!(element, TraceElement::Move(Move::Surface)))
+            .map(|segment| {
+                1 + segment
+                    .iter()
+                    .filter(|element| matches!(element, TraceElement::Move(Move::Directed(_))))
+                    .count()
+            })
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Sum of the directed moves since the last surface.
+    /// Silences are not included as their direction is unknown.
+    fn net_displacement(&self) -> Offset {
+        let start = self
+            .trace
+            .iter()
+            .rposition(|element| matches!(element, TraceElement::Move(Move::Surface)))
+            .map_or(0, |i| i + 1);
+        self.trace[start..]
+            .iter()
+            .filter_map(|element| match element {
+                TraceElement::Move(Move::Directed(direction)) => Some(direction.delta()),
+                _ => None,
+            })
+            .fold(Offset::ZERO, |acc, delta| acc + delta)
+    }
+
     fn undo_trace(&mut self) -> bool {
         self.trace.pop().is_some()
     }
@@ -210,73 +895,205 @@ impl Trace {
         self.trace.push(TraceElement::Intel(intel));
     }
 
+    pub const fn len(&self) -> usize {
+        self.trace.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.trace.is_empty()
+    }
+
+    /// The trace as it was after its first `len` elements.
+    fn prefix(&self, len: usize) -> Self {
+        Self {
+            trace: self.trace[..len.min(self.trace.len())].to_vec(),
+            silence_range: self.silence_range,
+            silence_weights: self.silence_weights.clone(),
+        }
+    }
+
+    /// Each intel with its index in the trace and the number of moves and mines before it.
+    fn intel_steps(&self) -> Vec<(usize, usize, &IntelQuestion)> {
+        let mut step = 0;
+        self.trace
+            .iter()
+            .enumerate()
+            .filter_map(|(i, element)| match element {
+                TraceElement::Intel(intel) => Some((i, step, intel)),
+                _ => {
+                    step += 1;
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The trace with only the elements for which `keep` returns `true`, given their index.
+    fn filtered(&self, keep: impl Fn(usize, &TraceElement) -> bool) -> Self {
+        Self {
+            trace: self
+                .trace
+                .iter()
+                .enumerate()
+                .filter(|&(i, element)| keep(i, element))
+                .map(|(_, element)| element.clone())
+                .collect(),
+            silence_range: self.silence_range,
+            silence_weights: self.silence_weights.clone(),
+        }
+    }
+
+    /// The trace with the element at `index` replaced by `elements`.
+    fn replaced(&self, index: usize, elements: &[TraceElement]) -> Self {
+        let mut trace = self.trace.clone();
+        trace.splice(index..=index, elements.iter().cloned());
+        Self {
+            trace,
+            silence_range: self.silence_range,
+            silence_weights: self.silence_weights.clone(),
+        }
+    }
+
+    /// Directions the silence at `index` could have gone in, `None` if it is not a silence.
+    fn silence_directions(&self, index: usize) -> Option<Vec<Direction>> {
+        let excluded: &[Direction] = match self.trace.get(index)? {
+            TraceElement::Move(Move::Dash) => &[],
+            TraceElement::Move(Move::DashExcluding(excluded)) => excluded,
+            _ => return None,
+        };
+        Some(
+            Direction::ALL
+                .into_iter()
+                .filter(|d| !excluded.contains(d))
+                .collect(),
+        )
+    }
+
+    /// Attaches intel to the position after `step` moves, where step 0 is the starting position.
+    fn add_intel_at(&mut self, step: usize, intel: IntelQuestion) -> Result<(), IntelError> {
+        let position = match step {
+            0 => 0,
+            _ => self
+                .trace
+                .iter()
+                .enumerate()
+                .filter(|(_, element)| matches!(element, TraceElement::Move(_)))
+                .nth(step - 1)
+                .map(|(i, _)| i + 1)
+                .ok_or(IntelError::NoSuchStep(step))?,
+        };
+
+        self.trace.insert(position, TraceElement::Intel(intel));
+        Ok(())
+    }
+
     fn plant_mine(&mut self) {
         self.trace.push(TraceElement::Mine);
     }
 
+    fn silence_weight(&self, distance: usize) -> f64 {
+        self.silence_weights.get(distance).copied().unwrap_or(1.0)
+    }
+
     pub fn paths(&self) -> Vec<Vec<OffsetWithIntel>> {
-        let mut paths = vec![vec![OffsetWithIntel {
-            offset: Offset::ZERO,
-            intel: vec![],
-            has_mine: false,
-        }]];
+        self.weighted_paths()
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect()
+    }
+
+    /// Like [`Trace::paths`], with each path weighted by the likelihood of its silence distances.
+    pub fn weighted_paths(&self) -> Vec<(f64, Vec<OffsetWithIntel>)> {
+        let mut paths = vec![(
+            1.0,
+            vec![OffsetWithIntel {
+                offset: Offset::ZERO,
+                intel: vec![],
+                has_mine: false,
+                surfaced: false,
+            }],
+        )];
 
         for m in &self.trace {
             match m {
                 TraceElement::Move(Move::Directed(direction)) => {
-                    for path in &mut paths {
+                    paths.retain(|(_, path)| !Self::self_intersects(path, *direction));
+                    for (_, path) in &mut paths {
                         let last = path.last().unwrap();
                         let next = OffsetWithIntel {
                             offset: last.offset + direction.delta(),
                             intel: vec![],
                             has_mine: false,
+                            surfaced: false,
                         };
                         path.push(next);
                     }
                 }
-                TraceElement::Move(Move::Dash) => {
+                TraceElement::Move(r#move @ (Move::Dash | Move::DashExcluding(_))) => {
+                    let excluded: &[Direction] = match r#move {
+                        Move::DashExcluding(excluded) => excluded,
+                        _ => &[],
+                    };
                     let mut new_paths = vec![];
 
-                    for path in &paths {
-                        for direction in &[
-                            Direction::North,
-                            Direction::East,
-                            Direction::South,
-                            Direction::West,
-                        ] {
+                    for (weight, path) in &paths {
+                        for direction in Direction::ALL.iter().filter(|d| !excluded.contains(d)) {
                             let mut new_path = path.clone();
 
-                            for _ in 0..4 {
+                            for distance in 1..=self.silence_range as usize {
                                 let last = new_path.last().unwrap();
                                 let next = OffsetWithIntel {
                                     offset: last.offset + direction.delta(),
                                     intel: vec![],
                                     has_mine: false,
+                                    surfaced: false,
                                 };
 
-                                if new_path.iter().any(|p| p.offset == next.offset) {
+                                // A straight segment never revisits its own cells, so a match
+                                // here is always a genuine crossing of the prior trail.
+                                if Self::trail(&new_path)
+                                    .iter()
+                                    .any(|p| p.offset == next.offset)
+                                {
                                     break;
                                 }
                                 new_path.push(next);
-                                new_paths.push(new_path.clone());
+                                new_paths.push((
+                                    weight * self.silence_weight(distance),
+                                    new_path.clone(),
+                                ));
                             }
                         }
                     }
 
+                    // the paths so far are the ones that silenced without moving
+                    for (weight, _) in &mut paths {
+                        *weight *= self.silence_weight(0);
+                    }
                     paths.extend(new_paths);
                 }
                 TraceElement::Intel(intel) => {
-                    for path in &mut paths {
+                    for (_, path) in &mut paths {
                         let last = path.last_mut().unwrap();
-                        last.intel.push(*intel);
+                        last.intel.push(intel.clone());
                     }
                 }
                 TraceElement::Mine => {
-                    for path in &mut paths {
+                    for (_, path) in &mut paths {
                         let last = path.last_mut().unwrap();
                         last.has_mine = true;
                     }
                 }
+                TraceElement::Move(Move::Surface) => {
+                    for (_, path) in &mut paths {
+                        // step-local intel only holds until the enemy surfaces
+                        for p in path.iter_mut() {
+                            p.intel.retain(IntelQuestion::is_persistent);
+                        }
+                        let last = path.last_mut().unwrap();
+                        last.surfaced = true;
+                    }
+                }
             }
         }
 
@@ -284,16 +1101,78 @@ impl Trace {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Radar {
     map: Map,
     trace: Trace,
+    #[cfg_attr(feature = "serde", serde(default))]
+    ordering: OrderingMode,
+    /// rejects moves while no position is possible
+    #[cfg_attr(feature = "serde", serde(default))]
+    strict: bool,
+    /// known starting cell, eg. when tracking our own submarine
+    #[cfg_attr(feature = "serde", serde(default))]
+    origin: Option<Coordinate>,
+}
+
+/// Our own submarine ended up close to where an enemy mine could be, see [`Radar::register_move_near_mines`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MineWarning {
+    /// the submarine is on a cell where a mine could be
+    OnMine(Coordinate),
+    /// a mine could be at the given cell next to the submarine, diagonals included
+    NextToMine(Coordinate),
+}
+
+/// Differences between two radars tracking the same enemy, see [`Radar::compare`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparisonReport {
+    /// Index among the moves and mines of both traces where they first differ, `None` if they agree.
+    pub diverging_move: Option<usize>,
+    /// Trace indices of intel the other radar does not have.
+    pub intel_only_here: Vec<usize>,
+    pub intel_only_there: Vec<usize>,
+    pub positions_only_here: HashSet<Coordinate>,
+    pub positions_only_there: HashSet<Coordinate>,
+}
+
+impl ComparisonReport {
+    /// Whether both radars agree on everything.
+    pub fn is_empty(&self) -> bool {
+        self.diverging_move.is_none()
+            && self.intel_only_here.is_empty()
+            && self.intel_only_there.is_empty()
+            && self.positions_only_here.is_empty()
+            && self.positions_only_there.is_empty()
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CompareError {
+    #[error("The radars track on different maps")]
+    DifferentMaps,
+    #[error("The radars track on maps of different sizes")]
+    DifferentSizes,
+}
+
+/// Order in which [`Radar::get_possible_paths`] and friends yield paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderingMode {
+    /// By origin in reading order (see [`Coordinate`]'s `Ord`), then by the order of the trace's branches.
+    #[default]
+    RowMajorOrigin,
+    /// Shortest paths first, ties kept in [`OrderingMode::RowMajorOrigin`] order.
+    /// Paths differ in length only through silences.
+    ByPathLength,
 }
 
 #[derive(Debug, Clone, Copy, Hash)]
 pub struct DecoratedCoordinate {
     coord: Coordinate,
     has_mine: bool,
+    surfaced: bool,
 }
 
 impl DecoratedCoordinate {
@@ -304,105 +1183,903 @@ impl DecoratedCoordinate {
     pub const fn has_mine(&self) -> bool {
         self.has_mine
     }
+
+    /// Whether the enemy surfaced here, wiping the trail before it.
+    pub const fn surfaced(&self) -> bool {
+        self.surfaced
+    }
 }
 
 impl Radar {
+    pub const MAX_REACHABLE_TURNS: usize = 6;
+
     pub const fn new(map: Map) -> Self {
         Self {
             map,
             trace: Trace::new(),
+            ordering: OrderingMode::RowMajorOrigin,
+            strict: false,
+            origin: None,
         }
     }
 
-    pub fn register_move(&mut self, r#move: Move) -> Result<(), TraceMoveError> {
-        self.trace.make_move(r#move)
+    /// Pins where the tracked submarine started, so the trace yields at most one path.
+    /// Used to track our own submarine: [`Radar::legal_moves`] then tells which directions
+    /// do not cross our own trail.
+    pub const fn set_origin(&mut self, origin: Option<Coordinate>) {
+        self.origin = origin;
     }
 
-    /// Undo the last trace (move/intel). Returns `true` if there was a trace to undo.
-    pub fn undo_trace(&mut self) -> bool {
-        self.trace.undo_trace()
+    pub const fn origin(&self) -> Option<Coordinate> {
+        self.origin
     }
 
-    pub fn plant_mine(&mut self) {
-        self.trace.plant_mine();
+    pub const fn is_strict(&self) -> bool {
+        self.strict
     }
 
-    pub fn get_possible_paths(&self) -> impl Iterator<Item = Vec<DecoratedCoordinate>> + use<'_> {
-        let paths = self.trace.paths();
+    /// In strict mode moves are rejected with [`TraceMoveError::NoFeasiblePosition`] while no position
+    /// is possible, instead of deepening the contradiction. Off by default, to log moves freely.
+    pub const fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
 
-        (0..self.map.size)
-            .flat_map(|x| (0..self.map.size).map(move |y| Coordinate::new(x, y)))
-            .flat_map(move |origin| {
-                if self.map.obstacles.contains(&origin) {
-                    return vec![];
-                }
+    pub const fn ordering(&self) -> OrderingMode {
+        self.ordering
+    }
 
-                paths
-                    .iter()
-                    .filter_map(|path| {
-                        path.iter()
-                            .map(|p| {
-                                // check if we are a coordinate
-                                let Ok(coord) = (p.offset + origin.into()).try_into() else {
-                                    return None;
-                                };
+    /// Sets the order paths are yielded in. [`OrderingMode::ByPathLength`] computes all paths
+    /// before yielding the first one.
+    pub const fn set_ordering(&mut self, ordering: OrderingMode) {
+        self.ordering = ordering;
+    }
+
+    pub fn register_move(&mut self, r#move: Move) -> Result<(), TraceMoveError> {
+        if self.strict && self.get_possible_paths().next().is_none() {
+            return Err(TraceMoveError::NoFeasiblePosition);
+        }
+        self.trace.make_move(r#move)
+    }
+
+    /// Registers a move of our own submarine, tracked with [`Radar::set_origin`], and warns if it
+    /// ends on or next to one of `mines`, eg. [`Radar::opponent_mine_cells`] of the enemy's radar.
+    /// The move is registered either way. Without an origin the position is unknown and nothing is checked.
+    pub fn register_move_near_mines(
+        &mut self,
+        r#move: Move,
+        mines: &HashSet<Coordinate>,
+    ) -> Result<Option<MineWarning>, TraceMoveError> {
+        self.register_move(r#move)?;
+        if self.origin.is_none() {
+            return Ok(None);
+        }
+
+        let mut positions = self.possible_positions().into_iter().collect::<Vec<_>>();
+        positions.sort();
+        let on_mine = positions
+            .iter()
+            .find(|&&position| mines.contains(&position))
+            .map(|&position| MineWarning::OnMine(position));
+        let next_to_mine = || {
+            positions
+                .iter()
+                .flat_map(|&position| self.map.neighbours_8(position))
+                .find(|cell| mines.contains(cell))
+                .map(MineWarning::NextToMine)
+        };
+
+        Ok(on_mine.or_else(next_to_mine))
+    }
+
+    /// Registers the moves in order, stopping at the first one that is rejected.
+    /// Returns how many moves were registered, or the error if not even the first one was.
+    pub fn register_moves(&mut self, moves: &[Move]) -> Result<usize, TraceMoveError> {
+        for (i, r#move) in moves.iter().enumerate() {
+            if let Err(e) = self.register_move(r#move.clone()) {
+                return if i == 0 { Err(e) } else { Ok(i) };
+            }
+        }
+
+        Ok(moves.len())
+    }
+
+    /// Registers a silence that did not go in any of the `excluded` directions,
+    /// eg. because it was heard heading away from a wall.
+    pub fn register_silence_excluding(
+        &mut self,
+        excluded: &[Direction],
+    ) -> Result<(), TraceMoveError> {
+        self.register_move(Move::DashExcluding(excluded.to_vec()))
+    }
+
+    /// Registers a move log in the format of [`parse_log`], stopping at the first rejected move.
+    pub fn register_log(&mut self, log: &str) -> Result<(), LogError> {
+        for (index, element) in parse_log(log)?.into_iter().enumerate() {
+            match element {
+                TraceElement::Move(r#move) => self
+                    .register_move(r#move)
+                    .map_err(|source| LogError::Move { index, source })?,
+                TraceElement::Intel(intel) => self.trace.add_intel(intel),
+                TraceElement::Mine => self.plant_mine(),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Undo the last trace (move/intel). Returns `true` if there was a trace to undo.
+    pub fn undo_trace(&mut self) -> bool {
+        self.trace.undo_trace()
+    }
+
+    /// Undoes everything after the latest intel, keeping the intel itself.
+    /// Returns how many elements were removed, 0 if there is no intel in the trace.
+    pub fn undo_to_last_intel(&mut self) -> usize {
+        self.undo_to_last(|element| matches!(element, TraceElement::Intel(_)), true)
+    }
+
+    /// Undoes the latest surface and everything after it.
+    /// Returns how many elements were removed, 0 if the enemy never surfaced.
+    pub fn undo_to_last_surface(&mut self) -> usize {
+        self.undo_to_last(
+            |element| matches!(element, TraceElement::Move(Move::Surface)),
+            false,
+        )
+    }
+
+    fn undo_to_last(&mut self, target: impl Fn(&TraceElement) -> bool, keep: bool) -> usize {
+        let Some(last) = self.trace.trace.iter().rposition(target) else {
+            return 0;
+        };
+
+        let len = if keep { last + 1 } else { last };
+        let removed = self.trace.len() - len;
+        self.trace = self.trace.prefix(len);
+        removed
+    }
+
+    /// The latest silence, if its direction and distance are settled by what came after it,
+    /// ie. exactly one choice for it leaves any possible path. Returns its trace index, direction and distance.
+    /// Earlier silences are not looked at, as each choice costs a full path search.
+    /// Silences that may have stayed in place are never settled.
+    pub fn unambiguous_silence(&self) -> Option<(usize, Direction, u8)> {
+        let (index, directions) = (0..self.trace.len())
+            .rev()
+            .find_map(|index| Some((index, self.trace.silence_directions(index)?)))?;
+        let fits = |elements: &[TraceElement]| {
+            self.with_trace(self.trace.replaced(index, elements))
+                .get_possible_paths()
+                .next()
+                .is_some()
+        };
+
+        if fits(&[]) {
+            return None;
+        }
+
+        let mut feasible = directions
+            .into_iter()
+            .flat_map(|direction| {
+                (1..=self.trace.silence_range).map(move |distance| (direction, distance))
+            })
+            .filter(|&(direction, distance)| {
+                fits(&vec![
+                    TraceElement::Move(Move::Directed(direction));
+                    distance as usize
+                ])
+            });
+
+        match (feasible.next(), feasible.next()) {
+            (Some((direction, distance)), None) => Some((index, direction, distance)),
+            _ => None,
+        }
+    }
+
+    /// Replaces the silence at trace `index` with `distance` moves in `direction`, collapsing its branches.
+    pub fn commit_silence(
+        &mut self,
+        index: usize,
+        direction: Direction,
+        distance: u8,
+    ) -> Result<(), TraceMoveError> {
+        if self.trace.silence_directions(index).is_none() {
+            return Err(TraceMoveError::NotASilence(index));
+        }
+
+        self.trace = self.trace.replaced(
+            index,
+            &vec![TraceElement::Move(Move::Directed(direction)); distance as usize],
+        );
+        Ok(())
+    }
+
+    /// Removes all intel, keeping the moves and mines in order.
+    pub fn clear_intel(&mut self) {
+        self.trace = self
+            .trace
+            .filtered(|_, element| !matches!(element, TraceElement::Intel(_)));
+    }
+
+    /// Registers that the enemy laid a mine at its current position.
+    pub fn plant_mine(&mut self) {
+        self.trace.plant_mine();
+    }
+
+    /// Same as [`Radar::plant_mine`], named after the announcement it records.
+    /// Where the mine could be is given by [`Radar::opponent_mine_cells`].
+    pub fn register_opponent_mine(&mut self) {
+        self.plant_mine();
+    }
+
+    /// How far the enemy drifted with its directed moves since it last surfaced.
+    pub fn net_displacement(&self) -> Offset {
+        self.trace.net_displacement()
+    }
+
+    /// Directions in which the enemy cannot have silenced given the current trail.
+    pub fn silence_blocked_directions(&self) -> HashSet<Direction> {
+        self.trace.silence_blocked_directions()
+    }
+
+    pub const fn silence_range(&self) -> u8 {
+        self.trace.silence_range
+    }
+
+    /// Sets the maximum silence distance for variant rules. Applies to the whole trace.
+    pub const fn set_silence_range(&mut self, silence_range: u8) {
+        self.trace.silence_range = silence_range;
+    }
+
+    pub fn silence_weights(&self) -> &[f64] {
+        &self.trace.silence_weights
+    }
+
+    /// Replaces the map, eg. to correct an obstacle, keeping the trace.
+    pub fn set_map(&mut self, map: Map) {
+        self.map = map;
+    }
+
+    /// Sets how likely each silence distance is, indexed by distance, to weigh paths in the heatmap.
+    /// Distances without a weight weigh 1, so an empty vector treats all silences alike.
+    pub fn set_silence_weights(&mut self, weights: Vec<f64>) {
+        assert!(
+            weights.iter().all(|w| w.is_finite() && *w >= 0.0),
+            "Silence weights must be finite and non-negative"
+        );
+        self.trace.silence_weights = weights;
+    }
+
+    /// Checks what registering `move` would do without mutating the radar.
+    /// Returns whether the move would be accepted and the resulting number of possible positions.
+    pub fn preview_move(&self, r#move: &Move) -> (bool, usize) {
+        let mut radar = self.clone();
+        match radar.register_move(r#move.clone()) {
+            Ok(()) => (true, radar.count_possible_positions()),
+            Err(_) => (false, self.count_possible_positions()),
+        }
+    }
+
+    /// Computes all possible paths once for further querying.
+    pub fn analyze(&self) -> PossibilitySet {
+        PossibilitySet::from_weighted_decorated(&self.get_weighted_paths().collect::<Vec<_>>())
+    }
+
+    /// Moves registered since `kind` was last used, or since the start if it never was.
+    /// Hints at which systems could have charged up again.
+    pub fn moves_since_last(&self, kind: SystemKind) -> usize {
+        self.trace
+            .trace
+            .iter()
+            .rev()
+            .take_while(|element| !kind.used_in(element))
+            .filter(|element| matches!(element, TraceElement::Move(_)))
+            .count()
+    }
+
+    /// How many of the latest moves left the possible positions exactly as they were.
+    /// Intel that did not change the positions either is skipped over.
+    pub fn positions_unchanged_for(&self) -> usize {
+        let mut unchanged = 0;
+        let mut after = self.possible_positions();
+        for len in (0..self.trace.len()).rev() {
+            let before = self.trace_prefix(len).possible_positions();
+            if before != after {
+                break;
+            }
+            if matches!(self.trace.trace[len], TraceElement::Move(_)) {
+                unchanged += 1;
+            }
+            after = before;
+        }
+
+        unchanged
+    }
+
+    /// [`Radar::positions_unchanged_for`] from the count `unchanged` of `previous`, when this radar
+    /// adds a single element to its trace. Only whether both allow the `same_positions` is needed then,
+    /// instead of the positions after every prefix. `None` if this radar does not extend `previous` that way.
+    pub fn positions_unchanged_after(
+        &self,
+        previous: &Self,
+        unchanged: usize,
+        same_positions: bool,
+    ) -> Option<usize> {
+        let len = previous.trace.len();
+        if self.map != previous.map
+            || self.trace.len() != len + 1
+            || self.trace.prefix(len) != previous.trace
+        {
+            return None;
+        }
+
+        Some(match (same_positions, &self.trace.trace[len]) {
+            (false, _) => 0,
+            (true, TraceElement::Move(_)) => unchanged + 1,
+            (true, _) => unchanged,
+        })
+    }
+
+    /// Human readable one-line overview of the enemy position, for logs and chat bots.
+    pub fn summary(&self) -> String {
+        let possibilities = self.analyze();
+        let positions = possibilities.positions();
+
+        match positions.len() {
+            0 => "no possible positions".to_string(),
+            1 => format!("located at {}", positions.into_iter().next().unwrap()),
+            count => {
+                let (quadrant, probability) = possibilities
+                    .quadrant_distribution(&self.map)
+                    .into_iter()
+                    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .expect("there are four quadrants");
+                let label = |quadrant| self.map.quadrant_label().label(quadrant);
+                let mut summary = format!(
+                    "{count} positions, concentrated in quadrant {} ({probability:.2})",
+                    label(quadrant)
+                );
+                if let Some(drone) = possibilities.best_drone(&self.map) {
+                    summary.push_str(&format!(", best drone: quadrant {}", label(drone)));
+                }
+                summary.push_str(&format!(", entropy {:.1} bits", possibilities.entropy()));
+                summary
+            }
+        }
+    }
+
+    /// All coordinates the enemy can currently be at.
+    pub fn possible_positions(&self) -> HashSet<Coordinate> {
+        self.analyze().positions()
+    }
+
+    /// Whether the enemy can currently be at `coord`. Stops at the first path ending there,
+    /// which is cheaper than [`Radar::possible_positions`] for a single lookup.
+    pub fn is_possible_position(&self, coord: Coordinate) -> bool {
+        self.map.is_open(coord)
+            && self
+                .get_possible_paths()
+                .any(|path| path.last().is_some_and(|last| last.coord == coord))
+    }
 
-                                // check if we are in some quadrant
-                                let quadrant = self.map.quadrant_of(coord)?;
+    /// The possible positions as `(x, y, probability)` in reading order, to hand to a viewer
+    /// without sharing the trace. See [`crate::render::render_positions`] with the `image` feature.
+    pub fn positions_snapshot(&self) -> Vec<(u32, u32, f64)> {
+        let mut snapshot = self
+            .analyze()
+            .heatmap()
+            .iter()
+            .map(|(&coord, &probability)| (coord, probability))
+            .collect::<Vec<_>>();
+        snapshot.sort_by_key(|&(coord, _)| coord);
+        snapshot
+            .into_iter()
+            .map(|(coord, probability)| (coord.x, coord.y, probability))
+            .collect()
+    }
+
+    pub fn count_possible_positions(&self) -> usize {
+        self.analyze().count()
+    }
+
+    /// The radar as it was after the first `len` trace elements.
+    pub fn trace_prefix(&self, len: usize) -> Self {
+        self.with_trace(self.trace.prefix(len))
+    }
+
+    /// For each intel in the trace, its index and how many positions it eliminated when it was given.
+    pub fn intel_contribution(&self) -> Vec<(usize, usize)> {
+        self.trace
+            .trace
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| matches!(element, TraceElement::Intel(_)))
+            .map(|(index, _)| {
+                let before = self.trace_prefix(index).count_possible_positions();
+                let after = self.trace_prefix(index + 1).count_possible_positions();
+                (index, before.saturating_sub(after))
+            })
+            .collect()
+    }
+
+    /// Whether the possible positions after the first `step` trace elements are exactly `expected`.
+    pub fn assert_positions_at(&self, step: usize, expected: &HashSet<Coordinate>) -> bool {
+        self.trace_prefix(step).analyze().positions() == *expected
+    }
+
+    /// This radar's map and settings with another trace.
+    fn with_trace(&self, trace: Trace) -> Self {
+        Self {
+            map: self.map.clone(),
+            trace,
+            ordering: self.ordering,
+            strict: self.strict,
+            origin: self.origin,
+        }
+    }
 
+    /// Whether the trace visits more cells than the map has open water, in which case
+    /// no path can exist regardless of intel.
+    pub fn trace_exceeds_map(&self) -> bool {
+        self.trace.min_path_len() > self.map.open_count()
+    }
+
+    /// Localizes why there are no possible positions, or `None` if there are some.
+    /// Intel is ignored to find where the moves stop fitting, then removed one at a time, latest first,
+    /// to find a single contradicting piece.
+    ///
+    /// Any prefix of a fitting path fits too, so the moves are bisected rather than analyzed prefix
+    /// by prefix. Meant to run only once the positions are known to be empty.
+    pub fn diagnose_empty(&self) -> Option<EmptyReason> {
+        if self.count_possible_positions() > 0 {
+            return None;
+        }
+        if self.trace_exceeds_map() {
+            return Some(EmptyReason::MapTooSmall);
+        }
+
+        let is_intel = |element: &TraceElement| matches!(element, TraceElement::Intel(_));
+
+        let moves = self.with_trace(self.trace.filtered(|_, e| !is_intel(e)));
+        if moves.count_possible_positions() == 0 {
+            let fits = |len| {
+                moves
+                    .with_trace(moves.trace.prefix(len))
+                    .count_possible_positions()
+                    > 0
+            };
+            // the empty trace fits, the whole one does not
+            let (mut fitting, mut failing) = (0, moves.trace.len());
+            while failing - fitting > 1 {
+                let middle = fitting + (failing - fitting) / 2;
+                if fits(middle) {
+                    fitting = middle;
+                } else {
+                    failing = middle;
+                }
+            }
+
+            let index = (0..self.trace.len())
+                .filter(|&i| !is_intel(&self.trace.trace[i]))
+                .nth(failing - 1)
+                .expect("the moves-only trace is a subsequence of the trace");
+            return Some(EmptyReason::ForcedSurface { index });
+        }
+
+        self.trace
+            .trace
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, element)| is_intel(element))
+            .map(|(i, _)| i)
+            .find(|&index| {
+                self.with_trace(self.trace.filtered(|i, _| i != index))
+                    .count_possible_positions()
+                    > 0
+            })
+            .map(|index| EmptyReason::ContradictoryIntel { index })
+            .or(Some(EmptyReason::OverConstrained))
+    }
+
+    /// Whether both radars track the same trace on the same map and allow the same positions.
+    /// Settings that only affect the order of paths are ignored.
+    pub fn state_eq(&self, other: &Self) -> bool {
+        self.map == other.map
+            && self.trace == other.trace
+            && self.possible_positions() == other.possible_positions()
+    }
+
+    /// Positions possible here but not in `other`, and positions possible in `other` but not here.
+    pub fn position_diff(
+        &self,
+        other: &Self,
+    ) -> Result<(HashSet<Coordinate>, HashSet<Coordinate>), CompareError> {
+        if self.map.size != other.map.size {
+            return Err(CompareError::DifferentSizes);
+        }
+
+        let here = self.possible_positions();
+        let there = other.possible_positions();
+
+        Ok((
+            here.difference(&there).copied().collect(),
+            there.difference(&here).copied().collect(),
+        ))
+    }
+
+    /// Reports how this radar and `other` disagree, eg. to reconcile the notes of two teammates.
+    /// Intel counts as shared when the other radar has the same intel after the same number of moves.
+    ///
+    /// There is no share code format to decode, so this compares radars rather than codes;
+    /// a `compare_codes` would decode both codes into radars and call this.
+    pub fn compare(&self, other: &Self) -> Result<ComparisonReport, CompareError> {
+        if self.map != other.map {
+            return Err(CompareError::DifferentMaps);
+        }
+
+        let moves = |radar: &Self| {
+            radar
+                .trace
+                .trace
+                .iter()
+                .filter(|element| !matches!(element, TraceElement::Intel(_)))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let (here, there) = (moves(self), moves(other));
+        let diverging_move =
+            (0..here.len().max(there.len())).find(|&i| here.get(i) != there.get(i));
+
+        let (intel_here, intel_there) = (self.trace.intel_steps(), other.trace.intel_steps());
+        let intel_only = |intel: &[(usize, usize, &IntelQuestion)],
+                          other: &[(usize, usize, &IntelQuestion)]| {
+            intel
+                .iter()
+                .filter(|(_, step, intel)| {
+                    !other.iter().any(|(_, other_step, other_intel)| {
+                        (other_step, other_intel) == (step, intel)
+                    })
+                })
+                .map(|&(i, _, _)| i)
+                .collect()
+        };
+
+        let (positions_only_here, positions_only_there) = self.position_diff(other)?;
+        Ok(ComparisonReport {
+            diverging_move,
+            intel_only_here: intel_only(&intel_here, &intel_there),
+            intel_only_there: intel_only(&intel_there, &intel_here),
+            positions_only_here,
+            positions_only_there,
+        })
+    }
+
+    /// All paths the enemy could have taken, in the order set by [`Radar::set_ordering`].
+    pub fn get_possible_paths(&self) -> impl Iterator<Item = Vec<DecoratedCoordinate>> + use<'_> {
+        self.get_possible_paths_with_origins().map(|(_, path)| path)
+    }
+
+    /// Calls `f` with the cells of each possible path, without keeping more than one origin's paths
+    /// in memory at a time. [`OrderingMode::ByPathLength`] still has to collect all paths to sort them.
+    pub fn for_each_path(&self, mut f: impl FnMut(&[Coordinate])) {
+        let mut cells = Vec::new();
+        for path in self.get_possible_paths() {
+            cells.clear();
+            cells.extend(path.iter().map(DecoratedCoordinate::coord));
+            f(&cells);
+        }
+    }
+
+    /// Like [`Radar::get_possible_paths`], but also yields the origin each path starts at.
+    pub fn get_possible_paths_with_origins(
+        &self,
+    ) -> impl Iterator<Item = (Coordinate, Vec<DecoratedCoordinate>)> + use<'_> {
+        self.get_weighted_paths_with_origins()
+            .map(|(origin, _, path)| (origin, path))
+    }
+
+    /// Like [`Radar::get_possible_paths`], but also yields the weight of each path,
+    /// see [`Radar::set_silence_weights`].
+    pub fn get_weighted_paths(
+        &self,
+    ) -> impl Iterator<Item = (f64, Vec<DecoratedCoordinate>)> + use<'_> {
+        self.get_weighted_paths_with_origins()
+            .map(|(_, weight, path)| (weight, path))
+    }
+
+    /// Like [`Radar::get_possible_paths`], but reports the fraction of origins processed so far
+    /// to `progress` while iterating, starting at 0 and approaching 1.
+    pub fn get_possible_paths_with_progress<F: FnMut(f32)>(
+        &self,
+        progress: F,
+    ) -> impl Iterator<Item = Vec<DecoratedCoordinate>> + use<'_, F> {
+        self.get_weighted_paths_with_progress(progress)
+            .map(|(_, path)| path)
+    }
+
+    /// Combination of [`Radar::get_weighted_paths`] and [`Radar::get_possible_paths_with_progress`].
+    pub fn get_weighted_paths_with_progress<F: FnMut(f32)>(
+        &self,
+        progress: F,
+    ) -> impl Iterator<Item = (f64, Vec<DecoratedCoordinate>)> + use<'_, F> {
+        self.get_weighted_paths_with_origins_and_progress(progress)
+            .map(|(_, weight, path)| (weight, path))
+    }
+
+    fn get_weighted_paths_with_origins(
+        &self,
+    ) -> impl Iterator<Item = (Coordinate, f64, Vec<DecoratedCoordinate>)> + use<'_> {
+        self.get_weighted_paths_with_origins_and_progress(|_| ())
+    }
+
+    fn get_weighted_paths_with_origins_and_progress<F: FnMut(f32)>(
+        &self,
+        mut progress: F,
+    ) -> impl Iterator<Item = (Coordinate, f64, Vec<DecoratedCoordinate>)> + use<'_, F> {
+        // origins for which a path's bounding box leaves the board are never visited
+        let paths = self
+            .trace
+            .weighted_paths()
+            .into_iter()
+            .map(|(weight, path)| {
+                let fits = self.map.fitting_origins(path.iter().map(|p| p.offset));
+                (weight, path, fits)
+            })
+            .collect::<Vec<_>>();
+        let union = |ranges: Vec<&Range<u32>>| {
+            let start = ranges.iter().map(|range| range.start).min();
+            let end = ranges.iter().map(|range| range.end).max();
+            start.unwrap_or(0)..end.unwrap_or(0)
+        };
+        let (xs, ys) = match self.origin {
+            Some(origin) => (origin.x..origin.x + 1, origin.y..origin.y + 1),
+            None => (
+                union(paths.iter().map(|(_, _, (xs, _))| xs).collect()),
+                union(paths.iter().map(|(_, _, (_, ys))| ys).collect()),
+            ),
+        };
+        let origins = xs.len() as f32 * ys.len() as f32;
+
+        let mut row_major = ys
+            .flat_map(move |y| xs.clone().map(move |x| Coordinate::new(x, y)))
+            .enumerate()
+            .inspect(move |&(i, _)| progress(i as f32 / origins))
+            .flat_map(move |(_, origin)| {
+                if self.map.obstacles.contains(&origin) {
+                    return vec![];
+                }
+
+                paths
+                    .iter()
+                    .filter(|(_, _, (xs, ys))| xs.contains(&origin.x) && ys.contains(&origin.y))
+                    .filter_map(|(weight, path, _)| {
+                        // check if we stay on the board
+                        let coords = self.map.absolutize(path, origin)?;
+
+                        path.iter()
+                            .zip(coords)
+                            .map(|(p, coord)| {
                                 // check if we are on an obstacle
                                 if self.map.obstacles.contains(&coord) {
                                     return None;
                                 }
 
                                 // check if intel excludes this coordinate
-                                for intel in &p.intel {
-                                    match intel {
-                                        IntelQuestion::InQuadrant {
-                                            quadrant: question_quadrant,
-                                            answer,
-                                        } => {
-                                            let valid = match answer {
-                                                true => quadrant == *question_quadrant,
-                                                false => quadrant != *question_quadrant,
-                                            };
-
-                                            if !valid {
-                                                return None;
-                                            }
-                                        }
-                                        IntelQuestion::TruthLie { info1, info2 } => {
-                                            let check = |&info| match info {
-                                                InformationPiece::Quadrant(info_quadrant) => {
-                                                    info_quadrant == quadrant
-                                                }
-                                                InformationPiece::Column(column) => {
-                                                    coord.x == column
-                                                }
-                                                InformationPiece::Row(row) => coord.y == row,
-                                            };
-
-                                            // either both are true or both are false
-                                            if check(info1) == check(info2) {
-                                                return None;
-                                            }
-                                        }
-                                    }
+                                if !p
+                                    .intel
+                                    .iter()
+                                    .all(|intel| self.map.intel_holds(intel, coord))
+                                {
+                                    return None;
                                 }
 
                                 Some(DecoratedCoordinate {
                                     coord,
                                     has_mine: p.has_mine,
+                                    surfaced: p.surfaced,
                                 })
                             })
-                            .collect()
+                            .collect::<Option<Vec<_>>>()
+                            .map(|path| (origin, *weight, path))
                     })
-                    .collect()
+                    .collect::<Vec<_>>()
+            });
+
+        // exhausts the lazy iterator, so the chain below only yields the sorted paths
+        let sorted = match self.ordering {
+            OrderingMode::RowMajorOrigin => vec![],
+            OrderingMode::ByPathLength => {
+                let mut sorted = row_major.by_ref().collect::<Vec<_>>();
+                sorted.sort_by_key(|(_, _, path)| path.len());
+                sorted
+            }
+        };
+
+        sorted.into_iter().chain(row_major)
+    }
+
+    /// The number of possible positions if each intel were added on its own, in the same order.
+    /// Paths are only computed once, as intel about the current turn only depends on where a path ends.
+    pub fn evaluate_hypotheticals(&self, intels: &[IntelQuestion]) -> Vec<usize> {
+        let positions = self.possible_positions();
+        intels
+            .iter()
+            .map(|intel| {
+                positions
+                    .iter()
+                    .filter(|&&position| self.map.intel_holds(intel, position))
+                    .count()
             })
+            .collect()
+    }
+
+    /// Rejects intel about rows, columns or coordinates that are not on the map,
+    /// as it would silently match nothing.
+    fn check_intel_range(&self, intel: &IntelQuestion) -> Result<(), IntelError> {
+        match intel {
+            IntelQuestion::TruthLie { info1, info2 } => {
+                for &piece in [info1, info2] {
+                    match piece {
+                        InformationPiece::Column(i)
+                        | InformationPiece::Row(i)
+                        | InformationPiece::OutsideColumn(i)
+                        | InformationPiece::OutsideRow(i)
+                            if i >= self.map.size =>
+                        {
+                            return Err(IntelError::PieceOutOfRange(piece));
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            &IntelQuestion::LineHit { from: coord, .. } | &IntelQuestion::Cell { coord, .. }
+                if !self.map.contains(coord) =>
+            {
+                return Err(IntelError::CoordinateOutOfRange(coord));
+            }
+            &IntelQuestion::SharesRowWith(row) if row >= self.map.size => {
+                return Err(IntelError::PieceOutOfRange(InformationPiece::Row(row)));
+            }
+            &IntelQuestion::SharesColumnWith(column) if column >= self.map.size => {
+                return Err(IntelError::PieceOutOfRange(InformationPiece::Column(
+                    column,
+                )));
+            }
+            IntelQuestion::InQuadrant { .. }
+            | IntelQuestion::SurfacedInQuadrant { .. }
+            | IntelQuestion::NotInQuadrants { .. }
+            | IntelQuestion::LineHit { .. }
+            | IntelQuestion::Cell { .. }
+            | IntelQuestion::Half { .. }
+            | IntelQuestion::SharesRowWith(_)
+            | IntelQuestion::SharesColumnWith(_) => (),
+        }
+
+        Ok(())
     }
 
-    pub fn add_intel(&mut self, intel: IntelQuestion) {
+    pub fn add_intel(&mut self, intel: IntelQuestion) -> Result<(), IntelError> {
+        self.check_intel_range(&intel)?;
         self.trace.add_intel(intel);
+        Ok(())
+    }
+
+    /// Registers a drone that revealed the enemy's quadrant outright rather than answering yes or no.
+    /// Being in `revealed` already rules out the other quadrants, so this is a positive drone answer.
+    pub fn add_drone_reveal(&mut self, revealed: Quadrant) {
+        self.trace.add_intel(IntelQuestion::InQuadrant {
+            quadrant: revealed,
+            answer: true,
+        });
+    }
+
+    /// Adds intel about where the enemy was after `step` moves (0 being the starting position),
+    /// rather than about where it is now.
+    pub fn add_intel_at(&mut self, step: usize, intel: IntelQuestion) -> Result<(), IntelError> {
+        self.check_intel_range(&intel)?;
+        self.trace.add_intel_at(step, intel)
+    }
+
+    /// Adds the intel unless it contradicts earlier intel: it leaves no position possible,
+    /// yet some would remain if it was the only intel. Intel ruled out by the moves and the map alone
+    /// is added, as is any intel when there were no possible positions to begin with.
+    pub fn try_add_intel(&mut self, intel: IntelQuestion) -> Result<(), IntelError> {
+        self.check_intel_range(&intel)?;
+        let before = self.count_possible_positions();
+        self.trace.add_intel(intel.clone());
+
+        if before > 0 && self.count_possible_positions() == 0 {
+            let mut alone = self.clone();
+            alone.clear_intel();
+            alone.trace.add_intel(intel);
+
+            if alone.count_possible_positions() > 0 {
+                self.trace.undo_trace();
+                return Err(IntelError::Contradiction);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hit probability of a torpedo for every cell that could damage the enemy.
+    pub fn torpedo_targets(&self) -> HashMap<Coordinate, f64> {
+        self.analyze().torpedo_targets(&self.map)
+    }
+
+    /// The torpedo target most likely to damage the enemy. Ties are broken in reading order.
+    pub fn best_torpedo_target(&self) -> Option<(Coordinate, f64)> {
+        self.analyze().best_torpedo_target(&self.map)
+    }
+
+    /// Scores our own candidate cells by how unlikely the enemy is to be within torpedo range of them.
+    /// Safest cells come first, ties are broken in reading order.
+    pub fn safe_cells_for(&self, my_candidates: &[Coordinate]) -> Vec<(Coordinate, f64)> {
+        let possibilities = self.analyze();
+        let mut cells = my_candidates
+            .iter()
+            .map(|&cell| (cell, 1.0 - possibilities.torpedo_threat(cell)))
+            .collect::<Vec<_>>();
+        cells.sort_by(|(a, sa), (b, sb)| sb.total_cmp(sa).then(a.cmp(b)));
+        cells
+    }
+
+    /// Directions the enemy can still move in from at least one possible path.
+    pub fn legal_moves(&self) -> HashSet<Direction> {
+        self.analyze().legal_moves(&self.map)
+    }
+
+    /// Every starting cell from which at least one path survives. Unlike
+    /// [`Radar::possible_positions`] these are where the enemy began, not where it is.
+    pub fn possible_origins(&self) -> HashSet<Coordinate> {
+        self.get_possible_paths_with_origins()
+            .map(|(origin, _)| origin)
+            .collect()
+    }
+
+    /// The probability of each possible position combined with a prior, see [`PossibilitySet::heatmap_with_prior`].
+    pub fn heatmap_with_prior(&self, prior: &HashMap<Coordinate, f64>) -> HashMap<Coordinate, f64> {
+        self.analyze().heatmap_with_prior(prior)
+    }
+
+    /// Open cells inside the bounding box of the possible positions, in reading order.
+    /// Unlike [`Radar::possible_positions`] it fills the gaps, giving a contiguous area to sweep.
+    pub fn search_area(&self) -> Vec<Coordinate> {
+        self.analyze().search_area(&self.map)
+    }
+
+    /// How cornered the enemy is, from 0 when it could be anywhere on the map to 1 once it is located.
+    /// Based on the size of [`Radar::search_area`], 0 when there are no possible positions.
+    pub fn confinement_score(&self) -> f64 {
+        self.analyze().confinement_score(&self.map)
+    }
+
+    /// Cells where a mine laid by the enemy could be, across all possible paths.
+    /// A mine is laid on an open cell next to the position it was laid from, diagonals included.
+    pub fn opponent_mine_cells(&self) -> HashSet<Coordinate> {
+        self.get_possible_paths()
+            .flatten()
+            .filter(DecoratedCoordinate::has_mine)
+            .flat_map(|c| self.map.neighbours_8(c.coord()))
+            .filter(|&c| self.map.is_open(c))
+            .collect()
+    }
+
+    /// Cells the enemy could occupy after exactly one more directed move from any possible position.
+    pub fn reachable_next(&self) -> HashSet<Coordinate> {
+        self.reachable_within(1)
+    }
+
+    /// Cells the enemy could occupy after between 1 and `turns` more directed moves from any possible position.
+    /// The horizon is capped at [`Radar::MAX_REACHABLE_TURNS`] as the number of walks grows exponentially.
+    pub fn reachable_within(&self, turns: usize) -> HashSet<Coordinate> {
+        self.analyze().reachable_within(&self.map, turns)
+    }
+
+    /// Like [`Radar::reachable_within`], but the enemy may cross its own trail, as it can after surfacing.
+    /// Always a superset of [`Radar::reachable_within`], and not capped as it is a flood fill.
+    pub fn reachable_ignoring_trail(&self, turns: usize) -> HashSet<Coordinate> {
+        self.analyze().reachable_ignoring_trail(&self.map, turns)
     }
 
     pub const fn map(&self) -> &Map {
@@ -413,3 +2090,988 @@ impl Radar {
         &self.trace
     }
 }
+
+/// Asserts that two radars are [`Radar::state_eq`], listing the positions only one of them allows otherwise.
+#[cfg(test)]
+macro_rules! assert_radar_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        let (left, right): (&Radar, &Radar) = (&$left, &$right);
+        if !left.state_eq(right) {
+            let only = |here: &Radar, there: &Radar| {
+                let there = there.possible_positions();
+                let mut cells = here
+                    .possible_positions()
+                    .into_iter()
+                    .filter(|cell| !there.contains(cell))
+                    .collect::<Vec<_>>();
+                cells.sort();
+                cells.iter().map(ToString::to_string).collect::<Vec<_>>()
+            };
+            panic!(
+                "radars differ\n  only possible on the left: {:?}\n  only possible on the right: {:?}",
+                only(left, right),
+                only(right, left),
+            );
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_map(size: u32) -> Map {
+        Map::new(size, HashSet::new())
+    }
+
+    #[test]
+    fn truth_lie_keeps_cells_on_exactly_one_of_row_and_column_zero() {
+        let map = open_map(10);
+        let (row, column) = (InformationPiece::Row(0), InformationPiece::Column(0));
+        assert!(map.information_holds(Coordinate::new(3, 0), row));
+        assert!(!map.information_holds(Coordinate::new(3, 0), column));
+        assert!(map.information_holds(Coordinate::new(0, 3), column));
+        assert!(!map.information_holds(Coordinate::new(0, 3), row));
+
+        let cells = map.cells_satisfying(&IntelQuestion::TruthLie {
+            info1: row,
+            info2: column,
+        });
+        let expected = (1..10)
+            .flat_map(|i| [Coordinate::new(i, 0), Coordinate::new(0, i)])
+            .collect::<HashSet<_>>();
+        assert_eq!(cells, expected);
+    }
+
+    #[test]
+    fn undo_to_last_surface_and_intel_truncate_at_the_marker() {
+        let drone = IntelQuestion::InQuadrant {
+            quadrant: Quadrant::One,
+            answer: true,
+        };
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar.add_intel(drone.clone()).unwrap();
+        radar.register_move(Move::Surface).unwrap();
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar
+            .register_move(Move::Directed(Direction::South))
+            .unwrap();
+        let full = radar.trace().clone();
+
+        let mut before_surface = Radar::new(open_map(10));
+        before_surface
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        before_surface.add_intel(drone).unwrap();
+
+        assert_eq!(radar.undo_to_last_surface(), 3);
+        assert_radar_eq!(radar, before_surface);
+        assert_eq!(radar.undo_to_last_surface(), 0);
+
+        radar.trace = full;
+        assert_eq!(radar.undo_to_last_intel(), 3);
+        assert_radar_eq!(radar, before_surface);
+        assert_eq!(radar.undo_to_last_intel(), 0);
+    }
+
+    #[test]
+    fn toggling_an_obstacle_twice_restores_the_map() {
+        let mut map = open_map(5);
+        let cell = Coordinate::new(2, 2);
+        assert_eq!(map.toggle_obstacle(cell), Ok(true));
+        assert!(!map.is_open(cell));
+        assert_eq!(map.toggle_obstacle(cell), Ok(false));
+        assert!(map.is_open(cell));
+        assert_eq!(
+            map.toggle_obstacle(Coordinate::new(5, 0)),
+            Err(MapError::ObstacleOutOfBounds(Coordinate::new(5, 0)))
+        );
+    }
+
+    #[test]
+    fn surfacing_allows_crossing_the_old_trail() {
+        let mut radar = Radar::new(open_map(10));
+        for direction in [Direction::East, Direction::South, Direction::West] {
+            radar.register_move(Move::Directed(direction)).unwrap();
+        }
+        assert!(!radar.legal_moves().contains(&Direction::North));
+
+        radar.register_move(Move::Surface).unwrap();
+        assert!(radar.legal_moves().contains(&Direction::North));
+
+        radar
+            .register_move(Move::Directed(Direction::North))
+            .unwrap();
+        assert!(radar.count_possible_positions() > 0);
+        assert!(radar.legal_moves().contains(&Direction::East));
+    }
+
+    #[test]
+    fn persistent_intel_survives_a_surface() {
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar
+            .add_intel(IntelQuestion::InQuadrant {
+                quadrant: Quadrant::One,
+                answer: true,
+            })
+            .unwrap();
+        radar.register_move(Move::Surface).unwrap();
+
+        let quadrant_one = radar.map().quadrant_cells(Quadrant::One).collect();
+        assert!(radar.possible_positions().is_subset(&quadrant_one));
+    }
+
+    #[test]
+    fn step_local_intel_is_forgotten_at_a_surface() {
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar
+            .add_intel(IntelQuestion::Cell {
+                coord: Coordinate::new(4, 4),
+                present: true,
+            })
+            .unwrap();
+        assert_eq!(radar.count_possible_positions(), 1);
+
+        radar.register_move(Move::Surface).unwrap();
+        assert!(radar.count_possible_positions() > 1);
+    }
+
+    #[test]
+    fn state_eq_ignores_settings_that_only_order_paths() {
+        let mut left = Radar::new(open_map(10));
+        left.register_move(Move::Directed(Direction::East)).unwrap();
+        let mut right = left.clone();
+        right.set_ordering(OrderingMode::ByPathLength);
+        assert_radar_eq!(left, right);
+    }
+
+    #[test]
+    #[should_panic(expected = "only possible on the left: [\"a1\"]")]
+    fn assert_radar_eq_lists_the_differing_positions() {
+        let left = Radar::new(open_map(2));
+        let mut right = left.clone();
+        right
+            .add_intel(IntelQuestion::Cell {
+                coord: Coordinate::new(0, 0),
+                present: false,
+            })
+            .unwrap();
+        assert_radar_eq!(left, right);
+    }
+
+    #[test]
+    fn silence_alongside_the_trail_keeps_every_distance() {
+        let mut trace = Trace::new();
+        for direction in [Direction::East; 4].into_iter().chain([Direction::South]) {
+            trace.make_move(Move::Directed(direction)).unwrap();
+        }
+        trace.make_move(Move::Dash).unwrap();
+
+        // north is blocked by the trail, the other directions allow every distance
+        let ends = trace
+            .paths()
+            .iter()
+            .map(|path| path.last().unwrap().offset)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ends.len(),
+            1 + 3 * usize::from(Trace::DEFAULT_SILENCE_RANGE)
+        );
+
+        // running west right below the trail, all the way back under the start
+        assert!(ends.contains(&(Offset::ZERO + Direction::South.delta())));
+    }
+
+    #[test]
+    fn conflicting_drones_are_rejected() {
+        let drone = |answer| IntelQuestion::InQuadrant {
+            quadrant: Quadrant::Two,
+            answer,
+        };
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar.try_add_intel(drone(true)).unwrap();
+        let before = radar.clone();
+
+        assert!(matches!(
+            radar.try_add_intel(drone(false)),
+            Err(IntelError::Contradiction)
+        ));
+        assert_radar_eq!(radar, before);
+    }
+
+    #[test]
+    fn intel_ruled_out_by_the_moves_alone_is_kept() {
+        let mut radar = Radar::new(open_map(10));
+        for _ in 0..5 {
+            radar
+                .register_move(Move::Directed(Direction::East))
+                .unwrap();
+        }
+
+        // five moves east leave only the right half of the board
+        radar
+            .try_add_intel(IntelQuestion::InQuadrant {
+                quadrant: Quadrant::One,
+                answer: true,
+            })
+            .unwrap();
+        assert_eq!(radar.trace().len(), 6);
+        assert_eq!(radar.count_possible_positions(), 0);
+    }
+
+    #[test]
+    fn later_moves_settle_a_silence() {
+        let cell = |coord| IntelQuestion::Cell {
+            coord,
+            present: true,
+        };
+        let mut radar = Radar::new(open_map(10));
+        radar.register_move(Move::Dash).unwrap();
+        radar
+            .register_move(Move::Directed(Direction::South))
+            .unwrap();
+        assert_eq!(radar.unambiguous_silence(), None);
+
+        // starting in the corner and ending below d1, the silence went three cells east
+        radar.add_intel_at(0, cell(Coordinate::new(0, 0))).unwrap();
+        radar.add_intel(cell(Coordinate::new(3, 1))).unwrap();
+        assert_eq!(radar.unambiguous_silence(), Some((1, Direction::East, 3)));
+
+        let (index, direction, distance) = radar.unambiguous_silence().unwrap();
+        radar.commit_silence(index, direction, distance).unwrap();
+        assert_eq!(radar.unambiguous_silence(), None);
+        assert_eq!(
+            radar.possible_positions(),
+            core::iter::once(Coordinate::new(3, 1)).collect()
+        );
+    }
+
+    #[test]
+    fn moves_that_keep_the_positions_are_counted() {
+        // wrapping around, every cell stays possible after any move
+        let mut radar = Radar::new(open_map(10).with_topology(MapTopology::Toroidal));
+        let mut unchanged = radar.positions_unchanged_for();
+        assert_eq!(unchanged, 0);
+
+        for (count, direction) in [Direction::East, Direction::East, Direction::South]
+            .into_iter()
+            .enumerate()
+        {
+            let previous = radar.clone();
+            radar.register_move(Move::Directed(direction)).unwrap();
+            unchanged = radar
+                .positions_unchanged_after(&previous, unchanged, true)
+                .unwrap();
+            assert_eq!(unchanged, count + 1);
+            assert_eq!(radar.positions_unchanged_for(), count + 1);
+        }
+
+        let previous = radar.clone();
+        radar
+            .add_intel(IntelQuestion::InQuadrant {
+                quadrant: Quadrant::One,
+                answer: true,
+            })
+            .unwrap();
+        assert_eq!(radar.positions_unchanged_for(), 0);
+        assert_eq!(
+            radar.positions_unchanged_after(&previous, unchanged, false),
+            Some(0)
+        );
+        assert_eq!(
+            radar.positions_unchanged_after(&radar.trace_prefix(1), unchanged, true),
+            None
+        );
+    }
+
+    #[test]
+    fn diagnose_empty_names_each_reason() {
+        let in_first_quadrant = |answer| IntelQuestion::InQuadrant {
+            quadrant: Quadrant::One,
+            answer,
+        };
+
+        let mut radar = Radar::new(open_map(10));
+        assert_eq!(radar.diagnose_empty(), None);
+
+        let mut long = Radar::new(open_map(2));
+        for _ in 0..4 {
+            long.register_move(Move::Directed(Direction::East)).unwrap();
+        }
+        assert_eq!(long.diagnose_empty(), Some(EmptyReason::MapTooSmall));
+
+        // the intel in front shifts the index of the move that stops fitting
+        let mut narrow = Radar::new(open_map(3));
+        narrow.add_intel(in_first_quadrant(true)).unwrap();
+        for _ in 0..3 {
+            narrow
+                .register_move(Move::Directed(Direction::East))
+                .unwrap();
+        }
+        assert_eq!(
+            narrow.diagnose_empty(),
+            Some(EmptyReason::ForcedSurface { index: 3 })
+        );
+
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar.add_intel(in_first_quadrant(true)).unwrap();
+        radar.add_intel(in_first_quadrant(false)).unwrap();
+        assert_eq!(
+            radar.diagnose_empty(),
+            Some(EmptyReason::ContradictoryIntel { index: 2 })
+        );
+
+        // removing any single piece still leaves one of each answer
+        radar.add_intel(in_first_quadrant(true)).unwrap();
+        radar.add_intel(in_first_quadrant(false)).unwrap();
+        assert_eq!(radar.diagnose_empty(), Some(EmptyReason::OverConstrained));
+    }
+
+    #[test]
+    fn compare_reports_diverging_moves_and_intel_by_step() {
+        let in_first_quadrant = IntelQuestion::InQuadrant {
+            quadrant: Quadrant::One,
+            answer: true,
+        };
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+
+        assert!(radar.compare(&radar).unwrap().is_empty());
+        assert_eq!(
+            radar.compare(&Radar::new(open_map(5))),
+            Err(CompareError::DifferentMaps)
+        );
+
+        let mut with_intel = radar.clone();
+        with_intel.add_intel(in_first_quadrant.clone()).unwrap();
+        let report = with_intel.compare(&radar).unwrap();
+        assert_eq!(report.diverging_move, None);
+        assert_eq!(report.intel_only_here, [1]);
+        assert!(report.intel_only_there.is_empty());
+        assert!(report.positions_only_here.is_empty());
+        assert_eq!(report.positions_only_there.len(), 90 - 20);
+
+        // the same intel given after a different number of moves is not shared
+        let mut earlier = with_intel.clone();
+        earlier
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        let mut later = radar.clone();
+        later
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        later.add_intel(in_first_quadrant).unwrap();
+        let report = earlier.compare(&later).unwrap();
+        assert_eq!(report.diverging_move, None);
+        assert_eq!(report.intel_only_here, [1]);
+        assert_eq!(report.intel_only_there, [2]);
+
+        let mut south = radar.clone();
+        south
+            .register_move(Move::Directed(Direction::South))
+            .unwrap();
+        let mut east = radar;
+        east.register_move(Move::Directed(Direction::East)).unwrap();
+        assert_eq!(east.compare(&south).unwrap().diverging_move, Some(1));
+    }
+
+    #[test]
+    fn preview_move_matches_registering_it() {
+        let mut radar = Radar::new(open_map(5));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        let before = radar.clone();
+
+        for r#move in Direction::ALL
+            .map(Move::Directed)
+            .into_iter()
+            .chain([Move::Dash, Move::Surface])
+        {
+            let preview = radar.preview_move(&r#move);
+            let mut registered = radar.clone();
+            let expected = match registered.register_move(r#move.clone()) {
+                Ok(()) => (true, registered.count_possible_positions()),
+                Err(_) => (false, radar.count_possible_positions()),
+            };
+            assert_eq!(preview, expected, "{move:?}");
+        }
+        // going back west would cross the trail
+        assert_eq!(
+            radar.preview_move(&Move::Directed(Direction::West)),
+            (false, 20)
+        );
+        assert_radar_eq!(radar, before);
+    }
+
+    #[test]
+    fn silence_range_bounds_the_branches_and_their_reach() {
+        for range in [2, 5] {
+            let mut trace = Trace::new();
+            trace.silence_range = range;
+            trace.make_move(Move::Dash).unwrap();
+
+            let ends = trace
+                .paths()
+                .iter()
+                .map(|path| path.last().unwrap().offset)
+                .collect::<Vec<_>>();
+            // staying put, or up to `range` cells in each direction
+            assert_eq!(ends.len(), 1 + 4 * usize::from(range), "range {range}");
+            let farthest = ends
+                .iter()
+                .map(|offset| offset.x.abs() + offset.y.abs())
+                .max();
+            assert_eq!(farthest, Some(i32::from(range)), "range {range}");
+        }
+
+        // the radar threads its setting through to its trace
+        let mut radar = Radar::new(open_map(11));
+        radar.set_silence_range(2);
+        radar.register_move(Move::Dash).unwrap();
+        assert_eq!(radar.silence_range(), 2);
+        assert_eq!(radar.trace.paths().len(), 1 + 4 * 2);
+    }
+
+    #[test]
+    fn truth_lie_with_negated_pieces() {
+        let map = open_map(10);
+        let in_first = InformationPiece::Quadrant(Quadrant::One);
+        let not_row_three = InformationPiece::OutsideRow(3);
+        assert!(map.information_holds(Coordinate::new(5, 5), not_row_three));
+        assert!(!map.information_holds(Coordinate::new(5, 3), not_row_three));
+        assert_eq!(not_row_three.negated(), InformationPiece::Row(3));
+        assert!(not_row_three.is_negated() && !in_first.is_negated());
+
+        // truth: in quadrant 1, lie: not in row 3, or the other way round
+        let cells = map.cells_satisfying(&IntelQuestion::TruthLie {
+            info1: in_first,
+            info2: not_row_three,
+        });
+        let expected = (0..10)
+            .flat_map(|y| (0..10).map(move |x| Coordinate::new(x, y)))
+            .filter(|c| (c.x < 5 && c.y < 5) == (c.y == 3))
+            .collect::<HashSet<_>>();
+        assert_eq!(cells, expected);
+        assert!(cells.contains(&Coordinate::new(2, 3)));
+        assert!(cells.contains(&Coordinate::new(7, 7)));
+        assert!(!cells.contains(&Coordinate::new(7, 3)));
+        assert!(!cells.contains(&Coordinate::new(2, 2)));
+
+        // negating both pieces keeps the same cells, as the XOR is unchanged
+        let both_negated = map.cells_satisfying(&IntelQuestion::TruthLie {
+            info1: in_first.negated(),
+            info2: not_row_three.negated(),
+        });
+        assert_eq!(both_negated, cells);
+    }
+
+    #[test]
+    fn offsets_near_the_integer_limits_do_not_wrap() {
+        let max = Offset::new(i32::MAX, i32::MAX);
+        let min = Offset::new(i32::MIN, i32::MIN);
+        assert_eq!(max.checked_add(Offset::new(1, 0)), None);
+        assert_eq!(min.checked_add(Offset::new(0, -1)), None);
+        assert_eq!(
+            max.checked_add(Offset::new(-1, 0)),
+            Some(Offset::new(i32::MAX - 1, i32::MAX))
+        );
+
+        assert_eq!(Coordinate::try_from(min), Err(()));
+        assert_eq!(
+            Coordinate::try_from(max),
+            Ok(Coordinate::new(i32::MAX as u32, i32::MAX as u32))
+        );
+        assert_eq!(Offset::try_from(Coordinate::new(u32::MAX, 0)), Err(()));
+        assert_eq!(
+            Offset::try_from(Coordinate::new(i32::MAX as u32, 0)),
+            Ok(Offset::new(i32::MAX, 0))
+        );
+
+        let far = Coordinate::new(u32::MAX, u32::MAX);
+        assert_eq!(far + Offset::new(1, 0), None);
+        assert_eq!(Coordinate::new(0, 0) + Offset::new(0, -1), None);
+        assert_eq!(
+            far + min,
+            Some(Coordinate::new(i32::MAX as u32, i32::MAX as u32))
+        );
+        assert_eq!(far.neighbours().count(), 3);
+        assert_eq!(far.to_string(), "(4294967295, 4294967295)");
+    }
+
+    #[test]
+    fn map_builder_matches_map_new() {
+        let obstacles = [
+            Coordinate::new(1, 2),
+            Coordinate::new(4, 0),
+            Coordinate::new(3, 3),
+        ];
+        let built = MapBuilder::new(5)
+            .obstacle(obstacles[0])
+            .obstacles(obstacles[1..].iter().copied())
+            .build()
+            .unwrap();
+        assert_eq!(built, Map::new(5, HashSet::from_iter(obstacles)));
+
+        // "b3" is column 1, row 2
+        let from_notation = MapBuilder::new(5)
+            .obstacle_at("b3")
+            .obstacle_at("e1")
+            .obstacle_at("d4")
+            .build()
+            .unwrap();
+        assert_eq!(from_notation, built);
+    }
+
+    #[test]
+    fn map_builder_rejects_bad_obstacles() {
+        assert_eq!(
+            MapBuilder::new(5).obstacle(Coordinate::new(5, 0)).build(),
+            Err(MapError::ObstacleOutOfBounds(Coordinate::new(5, 0)))
+        );
+        assert_eq!(
+            MapBuilder::new(5).obstacle_at("a9").build(),
+            Err(MapError::ObstacleOutOfBounds(Coordinate::new(0, 8)))
+        );
+        assert!(matches!(
+            MapBuilder::new(5)
+                .obstacle_at("nowhere")
+                .obstacle_at("a1")
+                .build(),
+            Err(MapError::InvalidCoordinate(_))
+        ));
+    }
+
+    #[test]
+    fn intel_at_a_past_step_constrains_that_step() {
+        let mut radar = Radar::new(open_map(10));
+        for _ in 0..3 {
+            radar
+                .register_move(Move::Directed(Direction::East))
+                .unwrap();
+        }
+        let at = |x, y| IntelQuestion::Cell {
+            coord: Coordinate::new(x, y),
+            present: true,
+        };
+
+        let mut now = radar.clone();
+        now.add_intel(at(4, 0)).unwrap();
+        assert_eq!(
+            now.possible_positions(),
+            HashSet::from([Coordinate::new(4, 0)])
+        );
+
+        // after the first move it was at e1, so two moves later it is at g1
+        let mut past = radar.clone();
+        past.add_intel_at(1, at(4, 0)).unwrap();
+        assert_eq!(
+            past.possible_positions(),
+            HashSet::from([Coordinate::new(6, 0)])
+        );
+        assert!(matches!(
+            past.trace.trace[..],
+            [
+                TraceElement::Move(_),
+                TraceElement::Intel(_),
+                TraceElement::Move(_),
+                TraceElement::Move(_)
+            ]
+        ));
+
+        // step 0 is the starting position
+        let mut start = radar.clone();
+        start.add_intel_at(0, at(0, 5)).unwrap();
+        assert_eq!(
+            start.possible_positions(),
+            HashSet::from([Coordinate::new(3, 5)])
+        );
+
+        assert!(matches!(
+            radar.add_intel_at(4, at(0, 0)),
+            Err(IntelError::NoSuchStep(4))
+        ));
+    }
+
+    #[test]
+    fn reachable_within_follows_the_board_and_the_trail() {
+        // a ring around an island in the middle
+        let map = MapBuilder::new(3)
+            .obstacle(Coordinate::new(1, 1))
+            .build()
+            .unwrap();
+        let mut radar = Radar::new(map);
+        for _ in 0..2 {
+            radar
+                .register_move(Move::Directed(Direction::East))
+                .unwrap();
+        }
+        let c = Coordinate::new;
+        assert_eq!(
+            radar.possible_positions(),
+            HashSet::from([c(2, 0), c(2, 2)])
+        );
+
+        assert!(radar.reachable_within(0).is_empty());
+        assert_eq!(radar.reachable_within(1), HashSet::from([c(2, 1)]));
+        assert_eq!(radar.reachable_next(), radar.reachable_within(1));
+        // going back west would cross the trail, the island blocks the middle
+        assert_eq!(
+            radar.reachable_within(2),
+            HashSet::from([c(2, 0), c(2, 1), c(2, 2)])
+        );
+        assert_eq!(
+            radar.reachable_within(usize::MAX),
+            radar.reachable_within(Radar::MAX_REACHABLE_TURNS)
+        );
+    }
+
+    #[test]
+    fn legal_moves_close_in_along_a_coiled_trail() {
+        use Direction::*;
+
+        // spiralling inwards along the edge of a 4x4 board pins the start to the corner
+        let mut radar = Radar::new(open_map(4));
+        for direction in [
+            East, East, East, South, South, South, West, West, West, North, North,
+        ] {
+            radar.register_move(Move::Directed(direction)).unwrap();
+        }
+        assert_eq!(
+            radar.possible_positions(),
+            HashSet::from([Coordinate::new(0, 1)])
+        );
+        assert_eq!(radar.legal_moves(), HashSet::from([East]));
+
+        for (direction, legal) in [
+            (East, &[East, South][..]),
+            (East, &[South]),
+            (South, &[West]),
+            (West, &[]),
+        ] {
+            radar.register_move(Move::Directed(direction)).unwrap();
+            assert_eq!(
+                radar.legal_moves(),
+                legal.iter().copied().collect::<HashSet<_>>(),
+                "after {direction:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn silence_weights_shift_the_heatmap() {
+        let mut radar = Radar::new(open_map(9));
+        radar
+            .add_intel(IntelQuestion::Cell {
+                coord: Coordinate::new(4, 4),
+                present: true,
+            })
+            .unwrap();
+        radar.register_move(Move::Dash).unwrap();
+
+        let uniform = radar.analyze();
+        assert_eq!(uniform.count(), 17);
+        for probability in uniform.heatmap().values() {
+            assert!((probability - 1.0 / 17.0).abs() < 1e-9);
+        }
+
+        // silences favour the full distance of 4
+        radar.set_silence_weights(vec![1.0, 1.0, 1.0, 1.0, 10.0]);
+        let skewed = radar.analyze();
+        assert_eq!(skewed.positions(), uniform.positions());
+        let heatmap = skewed.heatmap();
+        let farthest = [(8, 4), (0, 4), (4, 8), (4, 0)]
+            .map(|(x, y)| heatmap[&Coordinate::new(x, y)])
+            .iter()
+            .sum::<f64>();
+        assert!((farthest - 40.0 / 53.0).abs() < 1e-9);
+        assert!((heatmap[&Coordinate::new(4, 4)] - 1.0 / 53.0).abs() < 1e-9);
+        let (likeliest, _) = skewed.top_positions(1)[0];
+        assert_eq!(likeliest.x().abs_diff(4) + likeliest.y().abs_diff(4), 4);
+    }
+
+    #[test]
+    fn toroidal_moves_off_the_east_edge_reappear_on_the_west() {
+        let torus = open_map(3).with_topology(MapTopology::Toroidal);
+        let east = Direction::East.delta();
+        assert_eq!(
+            torus.translate(Coordinate::new(2, 1), east),
+            Some(Coordinate::new(0, 1))
+        );
+        assert_eq!(
+            torus.translate(Coordinate::new(0, 0), Direction::North.delta()),
+            Some(Coordinate::new(0, 2))
+        );
+        assert_eq!(open_map(3).translate(Coordinate::new(2, 1), east), None);
+
+        let mut radar = Radar::new(torus);
+        radar
+            .add_intel(IntelQuestion::Cell {
+                coord: Coordinate::new(2, 1),
+                present: true,
+            })
+            .unwrap();
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        assert_eq!(
+            radar.possible_positions(),
+            HashSet::from([Coordinate::new(0, 1)])
+        );
+    }
+
+    #[test]
+    fn toroidal_wrap_may_not_cross_the_trail() {
+        let torus = open_map(3).with_topology(MapTopology::Toroidal);
+        let mut radar = Radar::new(torus);
+        for _ in 0..2 {
+            radar
+                .register_move(Move::Directed(Direction::East))
+                .unwrap();
+        }
+        assert_eq!(radar.count_possible_positions(), 9);
+
+        // a third move east wraps around onto the start of every path
+        let mut around = radar.clone();
+        around
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        assert_eq!(around.count_possible_positions(), 0);
+
+        // unless a surface wiped the trail first
+        radar.register_move(Move::Surface).unwrap();
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        assert_eq!(radar.count_possible_positions(), 9);
+    }
+
+    #[test]
+    fn progress_rises_from_zero_towards_one() {
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar.register_move(Move::Dash).unwrap();
+
+        let mut reported = vec![];
+        let paths = radar
+            .get_possible_paths_with_progress(|done| reported.push(done))
+            .count();
+        assert_eq!(paths, radar.get_possible_paths().count());
+
+        assert_eq!(reported.first(), Some(&0.0));
+        assert!(reported.windows(2).all(|pair| pair[0] <= pair[1]));
+        let last = *reported.last().unwrap();
+        assert!((0.9..=1.0).contains(&last), "ended at {last}");
+    }
+
+    #[test]
+    fn ordering_modes_fix_the_first_paths() {
+        let mut radar = Radar::new(open_map(3));
+        radar.register_move(Move::Dash).unwrap();
+        let first = |radar: &Radar| {
+            radar
+                .get_possible_paths()
+                .take(5)
+                .map(|path| {
+                    path.iter()
+                        .map(|c| c.coord().to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // every branch of the first origin, east before south
+        assert_eq!(radar.ordering(), OrderingMode::RowMajorOrigin);
+        assert_eq!(
+            first(&radar),
+            ["a1", "a1 b1", "a1 b1 c1", "a1 a2", "a1 a2 a3"]
+        );
+
+        // every origin staying put comes first, in reading order
+        radar.set_ordering(OrderingMode::ByPathLength);
+        assert_eq!(first(&radar), ["a1", "b1", "c1", "a2", "b2"]);
+        let lengths = radar
+            .get_possible_paths()
+            .map(|path| path.len())
+            .collect::<Vec<_>>();
+        assert!(lengths.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn skipping_origins_matches_the_naive_scan() {
+        let map = MapBuilder::new(10)
+            .obstacles([Coordinate::new(4, 4), Coordinate::new(7, 2)])
+            .build()
+            .unwrap();
+        let mut radar = Radar::new(map);
+        for direction in [
+            Direction::East,
+            Direction::East,
+            Direction::East,
+            Direction::South,
+        ] {
+            radar.register_move(Move::Directed(direction)).unwrap();
+        }
+        radar.register_move(Move::Dash).unwrap();
+
+        // every cell is tried as the origin of every path
+        let naive = |radar: &Radar| {
+            let mut paths = vec![];
+            for y in 0..10 {
+                for x in 0..10 {
+                    let origin = Coordinate::new(x, y);
+                    for path in radar.trace.paths() {
+                        let Some(coords) = radar.map.absolutize(&path, origin) else {
+                            continue;
+                        };
+                        if coords.iter().all(|&c| radar.map.is_open(c)) {
+                            paths.push(coords);
+                        }
+                    }
+                }
+            }
+            paths.sort();
+            paths
+        };
+        let skipping = |radar: &Radar| {
+            let mut paths = radar
+                .get_possible_paths()
+                .map(|path| {
+                    path.iter()
+                        .map(DecoratedCoordinate::coord)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        };
+        assert_eq!(skipping(&radar), naive(&radar));
+
+        // a single path 3 wide and 1 high fits 7 columns and 9 rows of origins
+        let straight = radar.trace_prefix(4);
+        let mut visited = 0;
+        let found = straight
+            .get_possible_paths_with_progress(|_| visited += 1)
+            .count();
+        assert_eq!(visited, 7 * 9);
+        assert_eq!(skipping(&straight), naive(&straight));
+        assert_eq!(found, straight.count_possible_positions());
+    }
+
+    #[test]
+    fn pinned_origin_tracks_a_single_path() {
+        use Direction::*;
+
+        let mut own = Radar::new(open_map(6));
+        own.set_origin(Some(Coordinate::new(2, 2)));
+        // coiling around the start
+        for direction in [North, East, South, South, West, West] {
+            own.register_move(Move::Directed(direction)).unwrap();
+        }
+        assert_eq!(own.get_possible_paths().count(), 1);
+        assert_eq!(
+            own.possible_positions(),
+            HashSet::from([Coordinate::new(1, 3)])
+        );
+        assert_eq!(own.legal_moves(), HashSet::from([North, South, West]));
+
+        // alongside the start, which is part of the trail too
+        own.register_move(Move::Directed(North)).unwrap();
+        assert_eq!(own.legal_moves(), HashSet::from([North, West]));
+
+        let mut cornered = Radar::new(open_map(6));
+        cornered.set_origin(Some(Coordinate::new(0, 0)));
+        assert_eq!(cornered.legal_moves(), HashSet::from([East, South]));
+        cornered.register_move(Move::Directed(West)).unwrap();
+        assert_eq!(cornered.count_possible_positions(), 0);
+    }
+
+    #[test]
+    fn hypotheticals_match_adding_each_intel() {
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar.register_move(Move::Dash).unwrap();
+        radar
+            .register_move(Move::Directed(Direction::South))
+            .unwrap();
+
+        let mut intels = Quadrant::ALL
+            .map(|quadrant| IntelQuestion::InQuadrant {
+                quadrant,
+                answer: true,
+            })
+            .to_vec();
+        intels.push(IntelQuestion::TruthLie {
+            info1: InformationPiece::Row(0),
+            info2: InformationPiece::OutsideColumn(9),
+        });
+        intels.push(IntelQuestion::Cell {
+            coord: Coordinate::new(5, 5),
+            present: false,
+        });
+
+        let individually = intels
+            .iter()
+            .map(|intel| {
+                let mut hypothetical = radar.clone();
+                hypothetical.add_intel(intel.clone()).unwrap();
+                hypothetical.count_possible_positions()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(radar.evaluate_hypotheticals(&intels), individually);
+        assert_eq!(
+            individually[..4].iter().sum::<usize>(),
+            radar.count_possible_positions()
+        );
+    }
+
+    #[test]
+    fn a_drone_contributes_more_than_a_later_sonar() {
+        let mut radar = Radar::new(open_map(10));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar
+            .add_intel(IntelQuestion::InQuadrant {
+                quadrant: Quadrant::One,
+                answer: true,
+            })
+            .unwrap();
+        radar
+            .register_move(Move::Directed(Direction::South))
+            .unwrap();
+        radar
+            .add_intel(IntelQuestion::TruthLie {
+                info1: InformationPiece::Row(1),
+                info2: InformationPiece::Column(9),
+            })
+            .unwrap();
+
+        // 90 positions narrowed to 20, then 20 to the 4 in the second row
+        assert_eq!(radar.intel_contribution(), [(1, 70), (3, 16)]);
+        assert_eq!(radar.count_possible_positions(), 4);
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgb, RgbImage};
+
+use crate::{
+    collections::{HashMap, HashSet},
+    radar::{Coordinate, Map, Radar},
+};
+
+/// Side length of a single cell in pixels.
+const CELL_SIZE: u32 = 16;
+
+const WATER: [u8; 3] = [16, 40, 72];
+const OBSTACLE: [u8; 3] = [96, 96, 96];
+const HEAT: [u8; 3] = [255, 64, 32];
+
+/// Rasterizes the heatmap as a png. Cells are shaded relative to the most likely position.
+pub fn heatmap_png(map: &Map, heatmap: &HashMap<Coordinate, f64>) -> Vec<u8> {
+    let max = heatmap.values().copied().fold(0.0, f64::max);
+    let side = map.size() * CELL_SIZE;
+
+    let image = RgbImage::from_fn(side, side, |x, y| {
+        let coord = Coordinate::new(x / CELL_SIZE, y / CELL_SIZE);
+        if map.obstacles().contains(&coord) {
+            return Rgb(OBSTACLE);
+        }
+
+        let heat = match heatmap.get(&coord) {
+            Some(&p) if max > 0.0 => p / max,
+            _ => 0.0,
+        };
+        Rgb([0, 1, 2].map(|i| {
+            let (water, hot) = (f64::from(WATER[i]), f64::from(HEAT[i]));
+            (hot - water).mul_add(heat, water) as u8
+        }))
+    });
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .expect("encoding a png in memory cannot fail");
+    png
+}
+
+/// Rasterizes a [`Radar::positions_snapshot`] of a map with `size` cells per side, like [`heatmap_png`].
+/// Obstacles are not part of a snapshot, so they are drawn as water.
+pub fn render_positions(size: u32, snapshot: &[(u32, u32, f64)]) -> Vec<u8> {
+    let heatmap = snapshot
+        .iter()
+        .map(|&(x, y, probability)| (Coordinate::new(x, y), probability))
+        .collect();
+    heatmap_png(&Map::new(size, HashSet::new()), &heatmap)
+}
+
+impl Radar {
+    /// One png heatmap per trace step, starting with the empty trace, showing how the
+    /// possible positions narrowed over the game.
+    pub fn render_timeline_frames(&self) -> Vec<Vec<u8>> {
+        (0..=self.trace().len())
+            .map(|len| heatmap_png(self.map(), self.trace_prefix(len).analyze().heatmap()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        intel::{IntelQuestion, Quadrant},
+        radar::{Direction, Move},
+    };
+
+    #[test]
+    fn timeline_has_a_frame_per_step_and_the_start() {
+        let mut radar = Radar::new(Map::new(4, HashSet::new()));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        radar
+            .add_intel(IntelQuestion::InQuadrant {
+                quadrant: Quadrant::One,
+                answer: true,
+            })
+            .unwrap();
+        radar.register_move(Move::Dash).unwrap();
+
+        let frames = radar.render_timeline_frames();
+        assert_eq!(frames.len(), radar.trace().len() + 1);
+        for frame in &frames {
+            let image = image::load_from_memory_with_format(frame, ImageFormat::Png).unwrap();
+            assert_eq!(
+                (image.width(), image.height()),
+                (4 * CELL_SIZE, 4 * CELL_SIZE)
+            );
+        }
+        // the drone narrows the positions, so its frame differs from the one before
+        assert_ne!(frames[1], frames[2]);
+
+        assert_eq!(
+            Radar::new(Map::new(4, HashSet::new()))
+                .render_timeline_frames()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    action::Action,
+    collections::HashSet,
+    intel::{InformationPiece, IntelQuestion, Quadrant},
+    radar::{Coordinate, Direction, Map, Trace},
+};
+
+/// Small deterministic generator (splitmix64), so scenarios are reproducible from their seed on every platform.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    const fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniformly in `0..n`, `n` must not be 0
+    const fn below(&mut self, n: u32) -> u32 {
+        (self.next() % n as u64) as u32
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u32) as usize]
+    }
+}
+
+/// A random game to practise tracking on, or to generate regression cases from.
+#[derive(Debug, Clone, Copy)]
+pub struct Scenario;
+
+impl Scenario {
+    const SIZES: [u32; 2] = [10, 15];
+    /// one in this many cells is an island
+    const OBSTACLE_RARITY: u32 = 15;
+    const MIN_TURNS: u32 = 15;
+    const MAX_TURNS: u32 = 40;
+
+    /// A map, the actions of an enemy moving on it and the cell it ends at, all determined by `seed`.
+    /// Moves never cross the trail, silences stay within [`Trace::DEFAULT_SILENCE_RANGE`] and
+    /// drones and sonars answer truthfully, so replaying the actions always keeps the true position possible.
+    /// The enemy never surfaces, so the game ends early if it gets stuck.
+    pub fn generate(seed: u64) -> (Map, Vec<Action>, HashSet<Coordinate>) {
+        let mut rng = Rng(seed);
+
+        let size = rng.pick(&Self::SIZES);
+        let obstacles = (0..size)
+            .flat_map(|y| (0..size).map(move |x| Coordinate::new(x, y)))
+            .filter(|_| rng.below(Self::OBSTACLE_RARITY) == 0)
+            .collect::<HashSet<_>>();
+        let map = Map::new(size, obstacles);
+
+        let open = (0..size)
+            .flat_map(|y| (0..size).map(move |x| Coordinate::new(x, y)))
+            .filter(|&c| map.is_open(c))
+            .collect::<Vec<_>>();
+        let mut at = rng.pick(&open);
+        let mut visited = core::iter::once(at).collect::<HashSet<_>>();
+        let mut actions = vec![];
+
+        let turns = Self::MIN_TURNS + rng.below(Self::MAX_TURNS - Self::MIN_TURNS + 1);
+        for _ in 0..turns {
+            let legal = Direction::ALL
+                .into_iter()
+                .filter(|&d| map.step(at, d).is_some_and(|next| !visited.contains(&next)))
+                .collect::<Vec<_>>();
+
+            // silences are rare as each one multiplies the paths to track
+            match rng.below(20) {
+                0 | 1 => actions.push(Action::Mine),
+                2 | 3 => actions.push(Action::Intel(Self::drone(&mut rng, &map, at))),
+                4 | 5 => actions.push(Action::Intel(Self::sonar(&mut rng, &map, at))),
+                6 => {
+                    let direction = rng.pick(&Direction::ALL);
+                    let distance = rng.below(u32::from(Trace::DEFAULT_SILENCE_RANGE) + 1);
+                    for _ in 0..distance {
+                        match map.step(at, direction) {
+                            Some(next) if !visited.contains(&next) => {
+                                at = next;
+                                visited.insert(at);
+                            }
+                            _ => break,
+                        }
+                    }
+                    actions.push(Action::Silence);
+                }
+                _ if legal.is_empty() => break,
+                _ => {
+                    let direction = rng.pick(&legal);
+                    at = map
+                        .step(at, direction)
+                        .expect("legal directions stay on the map");
+                    visited.insert(at);
+                    actions.push(Action::Move(direction));
+                }
+            }
+        }
+
+        (map, actions, core::iter::once(at).collect())
+    }
+
+    /// a truthful drone answer about a random quadrant
+    fn drone(rng: &mut Rng, map: &Map, at: Coordinate) -> IntelQuestion {
+        let quadrant = rng.pick(&Quadrant::ALL);
+        IntelQuestion::InQuadrant {
+            quadrant,
+            answer: map.quadrant_of(at) == Some(quadrant),
+        }
+    }
+
+    /// a sonar answer with one true and one false piece, in random order
+    fn sonar(rng: &mut Rng, map: &Map, at: Coordinate) -> IntelQuestion {
+        let quadrant = map.quadrant_of(at).expect("the enemy is on the map");
+        let other =
+            |rng: &mut Rng, truth: u32, count: u32| (truth + 1 + rng.below(count - 1)) % count;
+
+        let truth = match rng.below(3) {
+            0 => InformationPiece::Row(at.y()),
+            1 => InformationPiece::Column(at.x()),
+            _ => InformationPiece::Quadrant(quadrant),
+        };
+        let lie = match rng.below(3) {
+            0 => InformationPiece::Row(other(rng, at.y(), map.size())),
+            1 => InformationPiece::Column(other(rng, at.x(), map.size())),
+            _ => {
+                let index = Quadrant::ALL
+                    .iter()
+                    .position(|&q| q == quadrant)
+                    .unwrap_or(0);
+                InformationPiece::Quadrant(Quadrant::ALL[other(rng, index as u32, 4) as usize])
+            }
+        };
+
+        let (info1, info2) = if rng.below(2) == 0 {
+            (truth, lie)
+        } else {
+            (lie, truth)
+        };
+        IntelQuestion::TruthLie { info1, info2 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radar::{Move, Radar};
+
+    #[test]
+    fn replaying_keeps_the_true_position_possible() {
+        for seed in 0..20 {
+            let (map, actions, truth) = Scenario::generate(seed);
+            let mut radar = Radar::new(map);
+            for action in actions {
+                match action {
+                    Action::Move(direction) => {
+                        radar.register_move(Move::Directed(direction)).unwrap();
+                    }
+                    Action::Silence => radar.register_move(Move::Dash).unwrap(),
+                    Action::Mine => radar.plant_mine(),
+                    Action::Intel(intel) => radar.add_intel(intel).unwrap(),
+                    other => panic!("scenarios do not generate {other:?}"),
+                }
+            }
+
+            assert!(
+                truth.is_subset(&radar.possible_positions()),
+                "seed {seed} lost the true position"
+            );
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use crate::{action::Action, radar::Radar};
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("Invalid session name {0:?}")]
+    InvalidName(String),
+    #[error("Nothing saved under the name {0:?}")]
+    NotFound(String),
+    #[error("Session storage failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("Malformed session file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Named radars saved as JSON files in a single directory.
+/// Macros, ie. recorded sequences of actions, are kept in a `macros` subdirectory.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    const EXTENSION: &str = "json";
+    const MACRO_DIR: &str = "macros";
+
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Saves the radar under `name`, overwriting any previous session with the same name.
+    pub fn save(&self, name: &str, radar: &Radar) -> Result<(), SessionError> {
+        Self::write(&self.dir, name, radar)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Radar, SessionError> {
+        Self::read(&self.dir, name)
+    }
+
+    /// Names of all saved sessions, sorted alphabetically.
+    pub fn list(&self) -> Result<Vec<String>, SessionError> {
+        Self::names(&self.dir)
+    }
+
+    /// Saves the actions under `name`, overwriting any previous macro with the same name.
+    pub fn save_macro(&self, name: &str, actions: &[Action]) -> Result<(), SessionError> {
+        Self::write(&self.dir.join(Self::MACRO_DIR), name, actions)
+    }
+
+    pub fn load_macro(&self, name: &str) -> Result<Vec<Action>, SessionError> {
+        Self::read(&self.dir.join(Self::MACRO_DIR), name)
+    }
+
+    /// Names of all saved macros, sorted alphabetically.
+    pub fn list_macros(&self) -> Result<Vec<String>, SessionError> {
+        Self::names(&self.dir.join(Self::MACRO_DIR))
+    }
+
+    fn write(
+        dir: &Path,
+        name: &str,
+        value: &(impl Serialize + ?Sized),
+    ) -> Result<(), SessionError> {
+        let path = Self::path_of(dir, name)?;
+        fs::create_dir_all(dir)?;
+        fs::write(path, serde_json::to_string(value)?)?;
+        Ok(())
+    }
+
+    fn read<T: DeserializeOwned>(dir: &Path, name: &str) -> Result<T, SessionError> {
+        let path = Self::path_of(dir, name)?;
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(SessionError::NotFound(name.to_string()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    fn names(dir: &Path) -> Result<Vec<String>, SessionError> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut names = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == Self::EXTENSION) {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+
+        Ok(names)
+    }
+
+    fn path_of(dir: &Path, name: &str) -> Result<PathBuf, SessionError> {
+        Ok(dir
+            .join(Self::sanitize(name)?)
+            .with_extension(Self::EXTENSION))
+    }
+
+    /// Only keeps characters that are safe in a file name on all platforms.
+    fn sanitize(name: &str) -> Result<String, SessionError> {
+        let sanitized = name
+            .trim()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+                _ => '_',
+            })
+            .collect::<String>();
+
+        if sanitized.chars().all(|c| c == '_') {
+            return Err(SessionError::InvalidName(name.to_string()));
+        }
+
+        Ok(sanitized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collections::HashSet,
+        radar::{Direction, Map, Move},
+    };
+
+    /// A fresh store in the system's temporary directory, removed once dropped.
+    struct TempStore(SessionStore);
+
+    impl TempStore {
+        fn new(test: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("captain_sonar_{test}_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            Self(SessionStore::new(dir))
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.dir());
+        }
+    }
+
+    #[test]
+    fn sessions_round_trip_through_the_directory() {
+        let store = TempStore::new("sessions");
+        assert_eq!(store.0.list().unwrap(), Vec::<String>::new());
+
+        let mut radar = Radar::new(Map::new(10, HashSet::new()));
+        radar
+            .register_move(Move::Directed(Direction::East))
+            .unwrap();
+        store.0.save("second enemy", &radar).unwrap();
+        store
+            .0
+            .save("first", &Radar::new(Map::new(5, HashSet::new())))
+            .unwrap();
+
+        assert_eq!(store.0.list().unwrap(), ["first", "second_enemy"]);
+        let loaded = store.0.load("second enemy").unwrap();
+        assert!(loaded.state_eq(&radar));
+
+        // saving again under the same name overwrites
+        store.0.save("first", &radar).unwrap();
+        assert_eq!(store.0.list().unwrap().len(), 2);
+        assert!(store.0.load("first").unwrap().state_eq(&radar));
+    }
+
+    #[test]
+    fn missing_and_invalid_names_are_errors() {
+        let store = TempStore::new("errors");
+
+        assert!(matches!(
+            store.0.load("nothing"),
+            Err(SessionError::NotFound(name)) if name == "nothing"
+        ));
+        assert!(matches!(
+            store
+                .0
+                .save(" ./ ", &Radar::new(Map::new(5, HashSet::new()))),
+            Err(SessionError::InvalidName(_))
+        ));
+        assert_eq!(store.0.list_macros().unwrap(), Vec::<String>::new());
+
+        store
+            .0
+            .save_macro("east", &[Action::Move(Direction::East)])
+            .unwrap();
+        assert_eq!(store.0.list_macros().unwrap(), ["east"]);
+        // macros are not listed as sessions
+        assert_eq!(store.0.list().unwrap(), Vec::<String>::new());
+        assert!(matches!(
+            store.0.load_macro("east").unwrap()[..],
+            [Action::Move(Direction::East)]
+        ));
+    }
+}
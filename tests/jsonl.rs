@@ -0,0 +1,68 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use serde_json::Value;
+
+/// Runs the binary in `--jsonl` mode on `input` and parses every line it prints.
+fn run_jsonl(input: &str) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_captain_sonar"))
+        .arg("--jsonl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // closing stdin ends the input
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "exited with {}", output.status);
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn prints_a_line_per_applied_action() {
+    let updates = run_jsonl("e e\nnot a log\n{\"Move\":\"South\"}\nsurface\n");
+    assert_eq!(updates.len(), 5);
+
+    let actions = updates
+        .iter()
+        .map(|update| update["action"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        [
+            serde_json::json!({ "Move": "East" }),
+            serde_json::json!({ "Move": "East" }),
+            Value::Null,
+            serde_json::json!({ "Move": "South" }),
+            serde_json::json!("Surface"),
+        ]
+    );
+
+    // the unreadable line is reported and skipped
+    assert_eq!(updates[2]["line"], "not a log");
+    assert!(updates[2]["error"].is_string());
+
+    let applied = [&updates[0], &updates[1], &updates[3], &updates[4]];
+    for update in applied {
+        assert!(update["error"].is_null());
+        assert!(update["entropy"].as_f64().unwrap() > 0.0);
+        let top = update["top"].as_array().unwrap();
+        assert!(!top.is_empty() && top.len() <= 5);
+    }
+    let positions = applied.map(|update| update["positions"].as_u64().unwrap());
+    assert!(positions.windows(2).all(|pair| pair[0] >= pair[1]));
+    // surfacing does not move the submarine
+    assert_eq!(positions[2], positions[3]);
+}